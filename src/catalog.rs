@@ -0,0 +1,83 @@
+// the catalog of a database, mapping table names to their ids
+
+use std::collections::BTreeMap;
+
+use crate::JadbError;
+
+/// The catalog is saved as `catalog.jadb` in the database root.
+///
+/// It maps every table name to a stable id which is allocated by the library, so a table's id never changes between sessions.
+/// Ids of deleted tables are never given out again.
+pub(crate) struct Catalog {
+    path: String,
+    pub(crate) next_id: usize, // id of the next created table
+    pub(crate) tables: BTreeMap<usize, String>, // table names, by id
+}
+
+impl Catalog {
+    /// Loads the catalog of the database at the given root, or an empty one if there is none yet.
+    pub(crate) fn load(root: &str) -> Result<Catalog, JadbError> {
+        let path = format!("{}/{}", root, "catalog.jadb");
+        let mut catalog = Catalog {
+            path,
+            next_id: 0,
+            tables: BTreeMap::new(),
+        };
+        if !std::path::Path::new(&catalog.path).exists() {
+            return Ok(catalog);
+        }
+        let content = std::fs::read_to_string(&catalog.path)?;
+        let mut lines = content.lines();
+        if lines.next() != Some("jadb catalog") {
+            return Err(JadbError::Corrupt(format!(
+                "{} isn't a catalog",
+                catalog.path
+            )));
+        }
+        for line in lines {
+            let (key, value) = line
+                .split_once(": ")
+                .ok_or_else(|| JadbError::Corrupt(format!("invalid catalog line {}", line)))?;
+            let number = |s: &str| {
+                s.parse::<usize>()
+                    .map_err(|_| JadbError::Corrupt(format!("invalid catalog line {}", line)))
+            };
+            if key == "next id" {
+                catalog.next_id = number(value)?;
+            } else {
+                catalog.tables.insert(number(key)?, value.to_string());
+            }
+        }
+        if let Some(&id) = catalog.tables.keys().next_back() {
+            catalog.next_id = catalog.next_id.max(id + 1); // never give out an id twice
+        }
+        Ok(catalog)
+    }
+    /// Writes the catalog to disk. It is written to a synced temporary file first, so it can't be left half written.
+    pub(crate) fn save(&self) -> Result<(), JadbError> {
+        let mut content = format!("jadb catalog\nnext id: {}", self.next_id);
+        for (id, name) in &self.tables {
+            content.push_str(&format!("\n{}: {}", id, name));
+        }
+        crate::replace_file(&self.path, content.as_bytes())
+    }
+    /// Returns the id of the table with the given name.
+    pub(crate) fn id_of(&self, name: &str) -> Option<usize> {
+        self.tables
+            .iter()
+            .find(|(_, table)| table.as_str() == name)
+            .map(|(&id, _)| id)
+    }
+    /// Adds a table with a newly allocated id and returns the id.
+    pub(crate) fn allocate(&mut self, name: &str) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.tables.insert(id, name.to_string());
+        id
+    }
+    /// Adds a table with an id it already has, e.g. from its info file.
+    pub(crate) fn adopt(&mut self, name: &str, id: usize) {
+        self.tables.insert(id, name.to_string());
+        self.next_id = self.next_id.max(id + 1);
+    }
+}
@@ -0,0 +1,249 @@
+// the ciphers rows can be encrypted with
+//
+// Every table uses one algorithm, which is saved in the `cipher` line of its info file. Tables without that line were
+// created before there was a choice and use AES-128-GCM. Every row records the algorithm in its header as well,
+// so rows of plaintext tables can be read no matter which cipher is given.
+
+use std::hash::Hasher;
+
+use aes_gcm::aead::generic_array::typenum::Unsigned;
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::{Aead, AeadCore, Payload};
+use aes_gcm::{Aes128Gcm, Aes256Gcm};
+use chacha20poly1305::XChaCha20Poly1305;
+use siphasher::sip::SipHasher24;
+
+use crate::JadbError;
+
+const KEY_ID_MESSAGE: &[u8] = b"jadb key id";
+
+// keys of the checksum of plaintext rows, "jadb plaintext c" as two little endian numbers
+const CHECKSUM_KEY_0: u64 = u64::from_le_bytes(*b"jadb pla");
+const CHECKSUM_KEY_1: u64 = u64::from_le_bytes(*b"intext c");
+
+/// # Algorithm
+///
+/// The algorithms a table can be encrypted with.
+///
+/// ## Examples
+/// ```
+/// assert_eq!(jadb::Algorithm::from_name("aes-256-gcm"), Some(jadb::Algorithm::Aes256Gcm));
+/// assert_eq!(jadb::Algorithm::XChaCha20Poly1305.name(), "xchacha20-poly1305");
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// AES-128-GCM, the algorithm of tables created without a choice.
+    Aes128Gcm,
+    /// AES-256-GCM.
+    Aes256Gcm,
+    /// XChaCha20-Poly1305, with 24 byte nonces.
+    XChaCha20Poly1305,
+    /// No encryption, for tables without sensitive data. Rows are only protected by a checksum against accidental changes.
+    Plaintext,
+}
+
+impl Algorithm {
+    /// Returns the name of the algorithm, as used in the info file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Aes128Gcm => "aes-128-gcm",
+            Algorithm::Aes256Gcm => "aes-256-gcm",
+            Algorithm::XChaCha20Poly1305 => "xchacha20-poly1305",
+            Algorithm::Plaintext => "plaintext",
+        }
+    }
+    /// Returns the algorithm with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<Algorithm> {
+        [
+            Algorithm::Aes128Gcm,
+            Algorithm::Aes256Gcm,
+            Algorithm::XChaCha20Poly1305,
+            Algorithm::Plaintext,
+        ]
+        .into_iter()
+        .find(|algorithm| algorithm.name() == name)
+    }
+    /// Returns the length of the nonces of the algorithm in bytes.
+    pub fn nonce_len(&self) -> usize {
+        match self {
+            Algorithm::Aes128Gcm | Algorithm::Aes256Gcm => 12,
+            Algorithm::XChaCha20Poly1305 => 24,
+            Algorithm::Plaintext => 0,
+        }
+    }
+    /// Number of the algorithm in row headers.
+    pub(crate) fn id(&self) -> u8 {
+        match self {
+            Algorithm::Plaintext => 0,
+            Algorithm::Aes128Gcm => 1,
+            Algorithm::Aes256Gcm => 2,
+            Algorithm::XChaCha20Poly1305 => 3,
+        }
+    }
+    pub(crate) fn from_id(id: u8) -> Option<Algorithm> {
+        [
+            Algorithm::Plaintext,
+            Algorithm::Aes128Gcm,
+            Algorithm::Aes256Gcm,
+            Algorithm::XChaCha20Poly1305,
+        ]
+        .into_iter()
+        .find(|algorithm| algorithm.id() == id)
+    }
+}
+
+/// # RowCipher
+///
+/// A cipher rows can be encrypted with. It is implemented for `aes_gcm::Aes128Gcm`, `aes_gcm::Aes256Gcm`,
+/// `chacha20poly1305::XChaCha20Poly1305` and [`Plaintext`], and can be passed wherever jadb expects a cipher.
+///
+/// ## Examples
+/// ```
+/// use aes_gcm::{Aes256Gcm, Key};
+/// use aes_gcm::aead::NewAead;
+/// use jadb::RowCipher;
+///
+/// let cipher = Aes256Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaPdSgVkYp3s6v9y$B&"));
+///
+/// let con_enc = cipher.encrypt(&[0u8; 12], b"hi", b"").unwrap();
+///
+/// assert_eq!(cipher.decrypt(&[0u8; 12], &con_enc, b""), Some(b"hi".to_vec()));
+/// ```
+pub trait RowCipher {
+    /// Returns the algorithm of the cipher.
+    fn algorithm(&self) -> Algorithm;
+    /// Encrypts the plaintext and authenticates it together with the associated data.
+    ///
+    /// Returns `JadbError::Encrypt` if the nonce doesn't have the length of the algorithm's nonces.
+    fn encrypt(&self, nonce: &[u8], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, JadbError>;
+    /// Decrypts the ciphertext, returning `None` if it or the associated data was changed or it was encrypted with another key.
+    fn decrypt(&self, nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Option<Vec<u8>>;
+    /// Returns the id of the key used by the cipher, which is recorded in every row.
+    ///
+    /// The id is the start of the authentication tag of a fixed message encrypted with the all zero nonce, so it tells keys apart
+    /// without revealing them. Row nonces are random, so they don't collide with the all zero nonce.
+    fn key_id(&self) -> u32 {
+        let nonce = vec![0u8; self.algorithm().nonce_len()];
+        let con_enc = self
+            .encrypt(&nonce, KEY_ID_MESSAGE, &[])
+            .unwrap_or_default(); // can't fail for such a short message
+        let mut id = [0u8; 4];
+        if let Some(tag) = con_enc.get(KEY_ID_MESSAGE.len()..KEY_ID_MESSAGE.len() + 4) {
+            id.copy_from_slice(tag);
+        }
+        u32::from_le_bytes(id)
+    }
+}
+
+/// # Plaintext
+///
+/// The cipher of tables which aren't encrypted. Instead of an authentication tag, a checksum is appended to every row.
+#[derive(Copy, Clone, Debug)]
+pub struct Plaintext;
+
+impl RowCipher for Plaintext {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::Plaintext
+    }
+    fn encrypt(&self, nonce: &[u8], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, JadbError> {
+        if !nonce.is_empty() {
+            return Err(JadbError::Encrypt);
+        }
+        let mut content = plaintext.to_vec();
+        content.extend_from_slice(&checksum(plaintext, aad).to_le_bytes());
+        Ok(content)
+    }
+    fn decrypt(&self, nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+        if !nonce.is_empty() || ciphertext.len() < 8 {
+            return None;
+        }
+        let (plaintext, sum) = ciphertext.split_at(ciphertext.len() - 8);
+        if sum != checksum(plaintext, aad).to_le_bytes() {
+            return None;
+        }
+        Some(plaintext.to_vec())
+    }
+    fn key_id(&self) -> u32 {
+        0 // there is no key
+    }
+}
+
+impl RowCipher for Aes128Gcm {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::Aes128Gcm
+    }
+    fn encrypt(&self, nonce: &[u8], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, JadbError> {
+        aead_encrypt(self, nonce, plaintext, aad)
+    }
+    fn decrypt(&self, nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+        aead_decrypt(self, nonce, ciphertext, aad)
+    }
+}
+
+impl RowCipher for Aes256Gcm {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::Aes256Gcm
+    }
+    fn encrypt(&self, nonce: &[u8], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, JadbError> {
+        aead_encrypt(self, nonce, plaintext, aad)
+    }
+    fn decrypt(&self, nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+        aead_decrypt(self, nonce, ciphertext, aad)
+    }
+}
+
+impl RowCipher for XChaCha20Poly1305 {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::XChaCha20Poly1305
+    }
+    fn encrypt(&self, nonce: &[u8], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, JadbError> {
+        aead_encrypt(self, nonce, plaintext, aad)
+    }
+    fn decrypt(&self, nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+        aead_decrypt(self, nonce, ciphertext, aad)
+    }
+}
+
+fn aead_encrypt<A: Aead + AeadCore>(
+    cipher: &A,
+    nonce: &[u8],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, JadbError> {
+    if nonce.len() != A::NonceSize::USIZE {
+        return Err(JadbError::Encrypt);
+    }
+    let payload = Payload {
+        msg: plaintext,
+        aad,
+    };
+    cipher
+        .encrypt(GenericArray::from_slice(nonce), payload)
+        .map_err(|_| JadbError::Encrypt)
+}
+
+fn aead_decrypt<A: Aead + AeadCore>(
+    cipher: &A,
+    nonce: &[u8],
+    ciphertext: &[u8],
+    aad: &[u8],
+) -> Option<Vec<u8>> {
+    if nonce.len() != A::NonceSize::USIZE {
+        return None; // e.g. a row written with another algorithm
+    }
+    let payload = Payload {
+        msg: ciphertext,
+        aad,
+    };
+    cipher
+        .decrypt(GenericArray::from_slice(nonce), payload)
+        .ok()
+}
+
+fn checksum(plaintext: &[u8], aad: &[u8]) -> u64 {
+    let mut hasher = SipHasher24::new_with_keys(CHECKSUM_KEY_0, CHECKSUM_KEY_1);
+    hasher.write(&(aad.len() as u64).to_le_bytes());
+    hasher.write(aad);
+    hasher.write(plaintext);
+    hasher.finish()
+}
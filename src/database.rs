@@ -0,0 +1,958 @@
+use std::ops::RangeBounds;
+
+use crate::catalog::Catalog;
+use crate::key::{Keyfile, KEYFILE_NAME};
+use crate::{
+    info, init, search, search_contains, search_fuzzy, search_prefix, search_regex, search_text,
+    Algorithm, Field, FullText, HashStorage, Hit, IndexOptions, JadbError, Row, RowCipher, Rows,
+    Schema, Table, TextHit, Update, Value,
+};
+
+/// # Database
+///
+/// The database is a directory containing tables. It owns the hash storage, the cipher and a registry of its tables, so they don't have to be passed to every call.
+///
+/// Every table is a subdirectory of the database root and is initialized automatically when the database is opened.
+/// The ids of the tables are allocated by the database and saved in a catalog file, `catalog.jadb`, in the database root.
+///
+/// ## Examples
+/// ```
+/// use aes_gcm::{Aes128Gcm, Key};
+/// use aes_gcm::aead::NewAead;
+///
+/// # fn main() -> Result<(), jadb::JadbError> {
+/// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+///
+/// let mut db = jadb::Database::open("mydb", cipher)?;
+///
+/// let mut users = db.create_table("users")?;
+///
+/// users.write("hi\nyou", jadb::Row { pos: 0 })?; // write 'hi' and 'you' in seperate fields
+///
+/// assert_eq!(users.read(jadb::Row { pos: 0 })?, vec!["hi", "you"]);
+///
+/// std::fs::remove_dir_all("mydb")?; // delete database afterwards
+/// # Ok(())
+/// # }
+/// ```
+pub struct Database {
+    root: String,                    // directory containing the tables
+    cipher: Box<dyn RowCipher>,      // used for all encrypted tables
+    hash_storage: HashStorage,       // index of the contents of all tables
+    catalog: Catalog,                // table names and ids on disk
+    tables: Vec<Option<TableEntry>>, // registry, indexed by table id
+    keyfile: Option<String>,         // path of the keyfile, if opened with a passphrase
+}
+
+struct TableEntry {
+    name: String,
+    path: String,
+}
+
+/// # TableHandle
+///
+/// A handle to a table of a [`Database`]. It is handed out by [`Database::table()`] and [`Database::create_table()`] and uses the database's hash storage and cipher.
+pub struct TableHandle<'db> {
+    db: &'db mut Database,
+    id: usize,
+}
+
+impl Database {
+    /// # open()
+    ///
+    /// This opens the database at the given directory, creating it if it doesn't exist yet.
+    ///
+    /// Every table in the catalog is registered with its id and initialized with the given cipher, which can be any [`RowCipher`].
+    /// Tables which aren't in the catalog yet, e.g. because they were created without a catalog, are added to it in the order of their names.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::EmptyPath` if no path is given, `JadbError::Corrupt` if the catalog can't be read and any error of [`init()`] if a table can't be initialized.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// let db = jadb::Database::open("mydb_open", cipher)?;
+    ///
+    /// std::fs::remove_dir_all("mydb_open")?; // delete database afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open(root: &str, cipher: impl RowCipher + 'static) -> Result<Database, JadbError> {
+        let catalog = Database::load_catalog(root)?;
+        let mut db = Database {
+            root: root.to_string(),
+            cipher: Box::new(cipher),
+            hash_storage: HashStorage::new(),
+            catalog,
+            tables: vec![],
+            keyfile: None,
+        };
+        let ids: Vec<(usize, String)> = db
+            .catalog
+            .tables
+            .iter()
+            .map(|(&id, name)| (id, name.clone()))
+            .collect();
+        for (id, name) in ids {
+            db.register(id, name)?;
+        }
+        Ok(db)
+    }
+    /// # open_rotating()
+    ///
+    /// This opens a database whose key rotation was interrupted, e.g. by a crash during [`Database::rotate_key()`].
+    ///
+    /// The rotation is finished first: every row still encrypted with the old key is re-encrypted with the new one. Then the database is opened with the new key.
+    /// Databases opened with a passphrase finish interrupted rotations on their own.
+    ///
+    /// ## Errors
+    ///
+    /// Returns any error of [`Table::rotate_key()`] and [`Database::open()`].
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let old_cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    /// let new_cipher = Aes128Gcm::new(Key::from_slice(b"q3t6w9z$C&F)J@Nc"));
+    ///
+    /// let db = jadb::Database::open_rotating("mydb_open_rotating", &old_cipher, new_cipher)?;
+    ///
+    /// std::fs::remove_dir_all("mydb_open_rotating")?; // delete database afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open_rotating(
+        root: &str,
+        old: &dyn RowCipher,
+        new: impl RowCipher + 'static,
+    ) -> Result<Database, JadbError> {
+        let catalog = Database::load_catalog(root)?;
+        for (&id, name) in &catalog.tables {
+            let path = format!("{}/{}", root, name);
+            Table { path: &path, id }.rotate_key(old, &new)?;
+        }
+        Database::open(root, new)
+    }
+    fn load_catalog(root: &str) -> Result<Catalog, JadbError> {
+        // load the catalog and add tables which aren't in it yet
+        if root.is_empty() {
+            return Err(JadbError::EmptyPath);
+        }
+        std::fs::create_dir_all(root)?;
+        let mut catalog = Catalog::load(root)?;
+        let mut names: Vec<String> = vec![];
+        for entry in std::fs::read_dir(root)? {
+            let entry = entry?;
+            if entry.path().join("info.jadb").exists() {
+                // only directories with info file are tables
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        names.sort(); // give new tables the same ids every time
+        let old_len = catalog.tables.len();
+        catalog.tables.retain(|_, name| names.contains(name)); // forget tables deleted by hand
+        let mut changed = catalog.tables.len() != old_len;
+        for name in &names {
+            if catalog.id_of(name).is_some() {
+                continue;
+            }
+            let path = format!("{}/{}", root, name);
+            match info::value(&path, "id")? {
+                Some(id) => {
+                    // table knows its id, e.g. because the catalog wasn't saved after creating it
+                    let id = id.parse::<usize>().map_err(|_| {
+                        JadbError::Corrupt(format!("invalid id {} in {}", id, path))
+                    })?;
+                    if let Some(other) = catalog.tables.get(&id) {
+                        return Err(JadbError::Corrupt(format!(
+                            "tables {} and {} both have id {}",
+                            other, name, id
+                        )));
+                    }
+                    catalog.adopt(name, id);
+                }
+                None => {
+                    let id = catalog.allocate(name);
+                    info::set(&path, "id", &id.to_string())?; // pin id in info file
+                }
+            }
+            changed = true;
+        }
+        if changed {
+            catalog.save()?;
+        }
+        Ok(catalog)
+    }
+    /// # open_with_passphrase()
+    ///
+    /// This opens the database at the given directory with a passphrase, creating it if it doesn't exist yet.
+    ///
+    /// The rows are encrypted with a random key, which is saved in the keyfile `key.jadb` in the database root, encrypted with a key derived from the passphrase using Argon2id.
+    /// If the database doesn't have a keyfile yet, a new key is generated.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::WrongPassphrase` if the passphrase doesn't match the keyfile and any error of [`Database::open()`].
+    ///
+    /// ## Examples
+    /// ```
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let mut db = jadb::Database::open_with_passphrase("mydb_passphrase", "correct horse battery staple")?;
+    ///
+    /// db.create_table("users")?.write("hi", jadb::Row { pos: 0 })?;
+    ///
+    /// drop(db);
+    ///
+    /// let mut db = jadb::Database::open_with_passphrase("mydb_passphrase", "correct horse battery staple")?;
+    ///
+    /// assert_eq!(db.table("users")?.read(jadb::Row { pos: 0 })?, vec!["hi"]);
+    ///
+    /// std::fs::remove_dir_all("mydb_passphrase")?; // delete database afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open_with_passphrase(root: &str, passphrase: &str) -> Result<Database, JadbError> {
+        if root.is_empty() {
+            return Err(JadbError::EmptyPath);
+        }
+        std::fs::create_dir_all(root)?; // keyfile is saved in the root
+        Database::open_with_keyfile(root, &format!("{}/{}", root, KEYFILE_NAME), passphrase)
+    }
+    /// # open_with_keyfile()
+    ///
+    /// This opens the database at the given directory with a passphrase and a keyfile at another location, e.g. a separate drive.
+    ///
+    /// Works like [`Database::open_with_passphrase()`], but the keyfile is read from and created at the given path.
+    /// A new keyfile is only saved if the database can be opened with its key, so it isn't created for a database encrypted with another key.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::WrongPassphrase` if the passphrase doesn't match the keyfile, `JadbError::Corrupt` if the keyfile can't be read and any error of [`Database::open()`].
+    ///
+    /// ## Examples
+    /// ```
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let db = jadb::Database::open_with_keyfile("mydb_keyfile", "mydb_keyfile.key", "correct horse battery staple")?;
+    ///
+    /// std::fs::remove_dir_all("mydb_keyfile")?; // delete database afterwards
+    /// std::fs::remove_file("mydb_keyfile.key")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open_with_keyfile(
+        root: &str,
+        keyfile: &str,
+        passphrase: &str,
+    ) -> Result<Database, JadbError> {
+        if !std::path::Path::new(keyfile).exists() {
+            let (new_keyfile, cipher) = Keyfile::generate(passphrase)?;
+            let mut db = Database::open(root, cipher)?;
+            new_keyfile.save(keyfile)?; // only once the database could be opened with the new key
+            db.keyfile = Some(keyfile.to_string());
+            return Ok(db);
+        }
+        let mut loaded = Keyfile::load(keyfile)?;
+        let cipher = loaded.unwrap(passphrase)?;
+        let mut db = match loaded.unwrap_old(passphrase)? {
+            Some(old) => {
+                // the last key rotation was interrupted, finish it
+                let db = Database::open_rotating(root, &old, cipher)?;
+                loaded.finish_rotation();
+                loaded.save(keyfile)?;
+                db
+            }
+            None => Database::open(root, cipher)?,
+        };
+        db.keyfile = Some(keyfile.to_string());
+        Ok(db)
+    }
+    /// # change_passphrase()
+    ///
+    /// This changes the passphrase of a database opened with a passphrase. Only the keyfile is rewritten, the rows stay as they are.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::WrongPassphrase` if the old passphrase is wrong and `JadbError::NotFound` if the database wasn't opened with a passphrase.
+    ///
+    /// ## Examples
+    /// ```
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let db = jadb::Database::open_with_passphrase("mydb_change_passphrase", "old passphrase")?;
+    ///
+    /// db.change_passphrase("old passphrase", "new passphrase")?;
+    ///
+    /// std::fs::remove_dir_all("mydb_change_passphrase")?; // delete database afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn change_passphrase(
+        &self,
+        passphrase: &str,
+        new_passphrase: &str,
+    ) -> Result<(), JadbError> {
+        let path = self
+            .keyfile
+            .as_deref()
+            .ok_or_else(|| JadbError::NotFound(String::from("keyfile")))?;
+        let mut keyfile = Keyfile::load(path)?;
+        keyfile.rewrap(passphrase, new_passphrase)?;
+        keyfile.save(path)
+    }
+    /// # rotate_key()
+    ///
+    /// This re-encrypts all tables of the database with a new key and uses it from then on. See [`Table::rotate_key()`].
+    ///
+    /// If the rotation is interrupted, the database can't be opened with either key alone. Open it with [`Database::open_rotating()`] to finish the rotation.
+    /// Databases opened with a passphrase use [`Database::rotate_data_key()`] instead. The number of re-encrypted rows is returned.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::KeyfileInUse` if the database was opened with a passphrase and any error of [`Table::rotate_key()`].
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// let mut db = jadb::Database::open("mydb_rotate_key", cipher)?;
+    ///
+    /// db.create_table("users")?.write("hi", jadb::Row { pos: 0 })?;
+    ///
+    /// assert_eq!(db.rotate_key(Aes128Gcm::new(Key::from_slice(b"q3t6w9z$C&F)J@Nc")))?, 1);
+    ///
+    /// assert_eq!(db.table("users")?.read(jadb::Row { pos: 0 })?, vec!["hi"]);
+    ///
+    /// std::fs::remove_dir_all("mydb_rotate_key")?; // delete database afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rotate_key(&mut self, new: impl RowCipher + 'static) -> Result<usize, JadbError> {
+        if let Some(keyfile) = &self.keyfile {
+            // the keyfile would still contain the old key
+            return Err(JadbError::KeyfileInUse(keyfile.clone()));
+        }
+        let rotated = self.rotate_tables(&new)?;
+        self.cipher = Box::new(new);
+        Ok(rotated)
+    }
+    /// # rotate_data_key()
+    ///
+    /// This replaces the data key of a database opened with a passphrase with a new random one and re-encrypts all tables with it.
+    ///
+    /// The keyfile keeps the old data key until every row is re-encrypted, so an interrupted rotation is finished the next time the database is opened.
+    /// The passphrase stays the same. The number of re-encrypted rows is returned.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::WrongPassphrase` if the passphrase is wrong, `JadbError::NotFound` if the database wasn't opened with a passphrase,
+    /// `JadbError::RotationInProgress` if another rotation of the keyfile wasn't finished yet, which is done by opening the database again,
+    /// and any error of [`Table::rotate_key()`].
+    ///
+    /// ## Examples
+    /// ```
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let mut db = jadb::Database::open_with_passphrase("mydb_rotate_data_key", "correct horse battery staple")?;
+    ///
+    /// db.create_table("users")?.write("hi", jadb::Row { pos: 0 })?;
+    ///
+    /// assert_eq!(db.rotate_data_key("correct horse battery staple")?, 1);
+    ///
+    /// std::fs::remove_dir_all("mydb_rotate_data_key")?; // delete database afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rotate_data_key(&mut self, passphrase: &str) -> Result<usize, JadbError> {
+        let path = self
+            .keyfile
+            .clone()
+            .ok_or_else(|| JadbError::NotFound(String::from("keyfile")))?;
+        let mut keyfile = Keyfile::load(&path)?;
+        let new = keyfile.begin_rotation(passphrase)?;
+        keyfile.save(&path)?; // the new key must be saved before any row uses it
+        let rotated = self.rotate_tables(&new)?;
+        self.cipher = Box::new(new);
+        keyfile.finish_rotation();
+        keyfile.save(&path)?;
+        Ok(rotated)
+    }
+    /// # create_table()
+    ///
+    /// This creates a new table in the database and returns a handle to it.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::InvalidName` if the name can't be used as a directory name or contains a control character, like `\n`, and `JadbError::AlreadyExists` if the table already exists.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// let mut db = jadb::Database::open("mydb_create_table", cipher)?;
+    ///
+    /// db.create_table("users")?;
+    ///
+    /// std::fs::remove_dir_all("mydb_create_table")?; // delete database afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create_table(&mut self, name: &str) -> Result<TableHandle<'_>, JadbError> {
+        let algorithm = self.cipher.algorithm();
+        self.create_table_with(name, algorithm)
+    }
+    /// # create_table_with()
+    ///
+    /// This creates a new table in the database, whose rows are encrypted with the given algorithm, and returns a handle to it.
+    ///
+    /// The database has only one cipher, so the algorithm must either be the one of the cipher or [`Algorithm::Plaintext`] for tables without sensitive data.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::CipherMismatch` if the database's cipher uses another algorithm and any error of [`Database::create_table()`].
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// let mut db = jadb::Database::open("mydb_create_table_with", cipher)?;
+    ///
+    /// db.create_table_with("countries", jadb::Algorithm::Plaintext)?.write("Iceland", jadb::Row { pos: 0 })?;
+    ///
+    /// std::fs::remove_dir_all("mydb_create_table_with")?; // delete database afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create_table_with(
+        &mut self,
+        name: &str,
+        algorithm: Algorithm,
+    ) -> Result<TableHandle<'_>, JadbError> {
+        check_name(name)?;
+        if algorithm != Algorithm::Plaintext && algorithm != self.cipher.algorithm() {
+            return Err(JadbError::CipherMismatch {
+                expected: algorithm,
+                found: self.cipher.algorithm(),
+            });
+        }
+        if self.id_of(name).is_some() {
+            return Err(JadbError::AlreadyExists(name.to_string()));
+        }
+        let path = format!("{}/{}", self.root, name);
+        let id = self.catalog.next_id;
+        Table { path: &path, id }.create_with(algorithm)?; // info file contains the id and algorithm
+        self.catalog.allocate(name);
+        self.catalog.save()?;
+        self.register(id, name.to_string())?;
+        Ok(TableHandle { db: self, id })
+    }
+    /// # table()
+    ///
+    /// This returns a handle to the table with the given name.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NotFound` if the database doesn't have a table with this name.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// let mut db = jadb::Database::open("mydb_table", cipher)?;
+    ///
+    /// db.create_table("users")?;
+    ///
+    /// let mut users = db.table("users")?;
+    ///
+    /// users.write("hi", jadb::Row { pos: 0 })?;
+    ///
+    /// std::fs::remove_dir_all("mydb_table")?; // delete database afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn table(&mut self, name: &str) -> Result<TableHandle<'_>, JadbError> {
+        let id = self
+            .id_of(name)
+            .ok_or_else(|| JadbError::NotFound(name.to_string()))?;
+        Ok(TableHandle { db: self, id })
+    }
+    /// # drop_table()
+    ///
+    /// This deletes the table with the given name and removes it from the registry and the hash storage.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NotFound` if the database doesn't have a table with this name.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// let mut db = jadb::Database::open("mydb_drop_table", cipher)?;
+    ///
+    /// db.create_table("users")?;
+    ///
+    /// db.drop_table("users")?;
+    ///
+    /// std::fs::remove_dir_all("mydb_drop_table")?; // delete database afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn drop_table(&mut self, name: &str) -> Result<(), JadbError> {
+        let id = self
+            .id_of(name)
+            .ok_or_else(|| JadbError::NotFound(name.to_string()))?;
+        if let Some(entry) = &self.tables[id] {
+            Table {
+                path: &entry.path,
+                id,
+            }
+            .delete(&mut self.hash_storage)?;
+        }
+        self.tables[id] = None; // keep ids of other tables
+        self.catalog.tables.remove(&id);
+        self.catalog.save()?;
+        Ok(())
+    }
+    /// # table_names()
+    ///
+    /// This returns the names of all tables in the database, ordered by their id.
+    pub fn table_names(&self) -> Vec<&str> {
+        self.tables
+            .iter()
+            .flatten()
+            .map(|entry| entry.name.as_str())
+            .collect()
+    }
+    /// # search()
+    ///
+    /// Using this you can search all tables of the database for a string. See [`search()`](crate::search()).
+    pub fn search(&self, term: String) -> Vec<Hit> {
+        search(term, &self.hash_storage)
+    }
+    /// # search_prefix()
+    ///
+    /// Using this you can search all tables of the database for fields starting with a string. See [`search_prefix()`](crate::search_prefix()).
+    pub fn search_prefix(&self, prefix: String) -> Vec<Hit> {
+        search_prefix(prefix, &self.hash_storage)
+    }
+    /// # search_contains()
+    ///
+    /// Using this you can search all tables of the database for fields containing a string. See [`search_contains()`](crate::search_contains()).
+    pub fn search_contains(&self, text: String) -> Vec<Hit> {
+        search_contains(text, &self.hash_storage)
+    }
+    /// # search_regex()
+    ///
+    /// Using this you can search all tables of the database for fields matching a regular expression. See [`search_regex()`](crate::search_regex()).
+    pub fn search_regex(&self, pattern: String) -> Result<Vec<Hit>, JadbError> {
+        search_regex(pattern, &self.hash_storage)
+    }
+    /// # search_fuzzy()
+    ///
+    /// Using this you can search all tables of the database for fields which are at most `max_distance` edits away from a string. See [`search_fuzzy()`](crate::search_fuzzy()).
+    pub fn search_fuzzy(&self, term: String, max_distance: usize) -> Vec<Hit> {
+        search_fuzzy(term, max_distance, &self.hash_storage)
+    }
+    /// # search_text()
+    ///
+    /// Using this you can search the words of all tables of the database with a full-text index. See [`search_text()`](crate::search_text()).
+    pub fn search_text(&self, query: String) -> Vec<TextHit> {
+        search_text(query, &self.hash_storage)
+    }
+    /// # cipher()
+    ///
+    /// This returns the cipher used for all encrypted tables of the database.
+    pub fn cipher(&self) -> &dyn RowCipher {
+        self.cipher.as_ref()
+    }
+    fn rotate_tables(&self, new: &dyn RowCipher) -> Result<usize, JadbError> {
+        // re-encrypt all tables with the new key
+        let mut rotated = 0;
+        for (id, entry) in self.tables.iter().enumerate() {
+            if let Some(entry) = entry {
+                rotated += Table {
+                    path: &entry.path,
+                    id,
+                }
+                .rotate_key(self.cipher.as_ref(), new)?;
+            }
+        }
+        Ok(rotated)
+    }
+    fn id_of(&self, name: &str) -> Option<usize> {
+        self.tables
+            .iter()
+            .position(|entry| matches!(entry, Some(entry) if entry.name == name))
+    }
+    fn register(&mut self, id: usize, name: String) -> Result<(), JadbError> {
+        // add table to registry and put its contents into the hash storage
+        let path = format!("{}/{}", self.root, name);
+        init(
+            Table { path: &path, id },
+            &mut self.hash_storage,
+            self.cipher.as_ref(),
+        )?;
+        if self.tables.len() <= id {
+            self.tables.resize_with(id + 1, || None);
+        }
+        self.tables[id] = Some(TableEntry { name, path });
+        Ok(())
+    }
+}
+
+impl TableHandle<'_> {
+    /// # table()
+    ///
+    /// This returns the underlying [`Table`], e.g. for use with [`Row::length()`].
+    pub fn table(&self) -> Table<'_> {
+        Table {
+            path: path_of(&self.db.tables, self.id),
+            id: self.id,
+        }
+    }
+    /// # cipher()
+    ///
+    /// This returns the cipher of the database the table belongs to.
+    pub fn cipher(&self) -> &dyn RowCipher {
+        self.db.cipher.as_ref()
+    }
+    /// # write()
+    ///
+    /// This writes a row to the table. See [`Table::write()`].
+    pub fn write(&mut self, content: &str, row: Row) -> Result<(), JadbError> {
+        let db = &mut *self.db;
+        let table = Table {
+            path: path_of(&db.tables, self.id),
+            id: self.id,
+        };
+        table.write(content, row, &mut db.hash_storage, db.cipher.as_ref())
+    }
+    /// # write_fields()
+    ///
+    /// This writes a row whose fields are given one by one. See [`Table::write_fields()`].
+    pub fn write_fields(&mut self, fields: &[&str], row: Row) -> Result<(), JadbError> {
+        let db = &mut *self.db;
+        let table = Table {
+            path: path_of(&db.tables, self.id),
+            id: self.id,
+        };
+        table.write_fields(fields, row, &mut db.hash_storage, db.cipher.as_ref())
+    }
+    /// # insert()
+    ///
+    /// This writes a new row at the next free position of the table and returns it. See [`Table::insert()`].
+    pub fn insert(&mut self, content: &str) -> Result<Row, JadbError> {
+        let db = &mut *self.db;
+        let table = Table {
+            path: path_of(&db.tables, self.id),
+            id: self.id,
+        };
+        table.insert(content, &mut db.hash_storage, db.cipher.as_ref())
+    }
+    /// # insert_if_absent()
+    ///
+    /// This writes a new row only if the table has no row at this position yet. See [`Table::insert_if_absent()`].
+    pub fn insert_if_absent(&mut self, content: &str, row: Row) -> Result<(), JadbError> {
+        let db = &mut *self.db;
+        let table = Table {
+            path: path_of(&db.tables, self.id),
+            id: self.id,
+        };
+        table.insert_if_absent(content, row, &mut db.hash_storage, db.cipher.as_ref())
+    }
+    /// # update()
+    ///
+    /// This changes the fields of an existing row. See [`Table::update()`].
+    pub fn update(&mut self, updates: &[Update], row: Row) -> Result<(), JadbError> {
+        let db = &mut *self.db;
+        let table = Table {
+            path: path_of(&db.tables, self.id),
+            id: self.id,
+        };
+        table.update(updates, row, &mut db.hash_storage, db.cipher.as_ref())
+    }
+    /// # update_fields()
+    ///
+    /// This rewrites a row with the operators of the crate documentation. See [`Table::update_fields()`].
+    pub fn update_fields(&mut self, content: &str, row: Row) -> Result<(), JadbError> {
+        let db = &mut *self.db;
+        let table = Table {
+            path: path_of(&db.tables, self.id),
+            id: self.id,
+        };
+        table.update_fields(content, row, &mut db.hash_storage, db.cipher.as_ref())
+    }
+    /// # read()
+    ///
+    /// This reads a row of the table. See [`Table::read()`].
+    pub fn read(&self, row: Row) -> Result<Vec<String>, JadbError> {
+        self.table().read(row, self.db.cipher.as_ref())
+    }
+    /// # rows()
+    ///
+    /// This returns an iterator over all rows of the table, ordered by position. See [`Table::rows()`].
+    pub fn rows(&self) -> Result<Rows<'_>, JadbError> {
+        self.table().rows(self.db.cipher.as_ref())
+    }
+    /// # keys()
+    ///
+    /// This returns the positions of all rows of the table in order, without reading them. See [`Table::keys()`].
+    pub fn keys(&self) -> Result<Vec<Row>, JadbError> {
+        self.table().keys()
+    }
+    /// # scan()
+    ///
+    /// This reads at most `limit` rows of the table, starting at `start`. See [`Table::scan()`].
+    pub fn scan(&self, start: Row, limit: usize) -> Result<Vec<(Row, Vec<String>)>, JadbError> {
+        self.table().scan(start, limit, self.db.cipher.as_ref())
+    }
+    /// # scan_keys()
+    ///
+    /// This returns the positions of at most `limit` rows of the table, starting at `start`. See [`Table::scan_keys()`].
+    pub fn scan_keys(&self, start: Row, limit: usize) -> Result<Vec<Row>, JadbError> {
+        self.table().scan_keys(start, limit)
+    }
+    /// # write_values()
+    ///
+    /// This writes a row of typed values to the table. See [`Table::write_values()`].
+    pub fn write_values(&mut self, values: &[Value], row: Row) -> Result<(), JadbError> {
+        let db = &mut *self.db;
+        let table = Table {
+            path: path_of(&db.tables, self.id),
+            id: self.id,
+        };
+        table.write_values(values, row, &mut db.hash_storage, db.cipher.as_ref())
+    }
+    /// # read_values()
+    ///
+    /// This reads the typed values of a row of the table. See [`Table::read_values()`].
+    pub fn read_values(&self, row: Row) -> Result<Vec<Value>, JadbError> {
+        self.table().read_values(row, self.db.cipher.as_ref())
+    }
+    /// # search()
+    ///
+    /// This searches the table for a string. See [`Table::search()`].
+    pub fn search(&self, term: String) -> Vec<Hit> {
+        self.table().search(term, &self.db.hash_storage)
+    }
+    /// # search_prefix()
+    ///
+    /// This searches the table for fields starting with a string. See [`Table::search_prefix()`].
+    pub fn search_prefix(&self, prefix: String) -> Vec<Hit> {
+        self.table().search_prefix(prefix, &self.db.hash_storage)
+    }
+    /// # search_contains()
+    ///
+    /// This searches the table for fields containing a string. See [`Table::search_contains()`].
+    pub fn search_contains(&self, text: String) -> Vec<Hit> {
+        self.table().search_contains(text, &self.db.hash_storage)
+    }
+    /// # search_regex()
+    ///
+    /// This searches the table for fields matching a regular expression. See [`Table::search_regex()`].
+    pub fn search_regex(&self, pattern: String) -> Result<Vec<Hit>, JadbError> {
+        self.table().search_regex(pattern, &self.db.hash_storage)
+    }
+    /// # search_fuzzy()
+    ///
+    /// This searches the table for fields which are at most `max_distance` edits away from a string. See [`Table::search_fuzzy()`].
+    pub fn search_fuzzy(&self, term: String, max_distance: usize) -> Vec<Hit> {
+        self.table()
+            .search_fuzzy(term, max_distance, &self.db.hash_storage)
+    }
+    /// # search_text()
+    ///
+    /// This searches the words of the table's fields. See [`Table::search_text()`].
+    pub fn search_text(&self, query: String) -> Result<Vec<TextHit>, JadbError> {
+        self.table().search_text(query, &self.db.hash_storage)
+    }
+    /// # range()
+    ///
+    /// Using this you can find the rows whose field is within a range of numbers or timestamps. See [`Table::range()`].
+    pub fn range<V: Into<Value> + Clone>(
+        &self,
+        field: Field,
+        range: impl RangeBounds<V>,
+    ) -> Vec<Hit> {
+        self.table().range(field, range, &self.db.hash_storage)
+    }
+    /// # enable_full_text()
+    ///
+    /// This adds a full-text index to the table. See [`Table::enable_full_text()`].
+    pub fn enable_full_text(&mut self, options: FullText) -> Result<(), JadbError> {
+        let db = &mut *self.db;
+        let table = Table {
+            path: path_of(&db.tables, self.id),
+            id: self.id,
+        };
+        table.enable_full_text(options, &mut db.hash_storage)
+    }
+    /// # set_index_options()
+    ///
+    /// This changes how the values of the table are indexed. See [`Table::set_index_options()`].
+    pub fn set_index_options(&mut self, options: IndexOptions) -> Result<(), JadbError> {
+        let db = &mut *self.db;
+        let table = Table {
+            path: path_of(&db.tables, self.id),
+            id: self.id,
+        };
+        table.set_index_options(options, &mut db.hash_storage)
+    }
+    /// # set_schema()
+    ///
+    /// This gives the table named, typed columns. See [`Table::set_schema()`].
+    pub fn set_schema(&mut self, schema: Schema) -> Result<(), JadbError> {
+        self.table().set_schema(schema, &self.db.hash_storage)
+    }
+    /// # schema()
+    ///
+    /// This returns the schema of the table, if it has one. See [`Table::schema()`].
+    pub fn schema(&self) -> Result<Option<Schema>, JadbError> {
+        self.table().schema()
+    }
+    /// # field()
+    ///
+    /// This returns the field of the column with the given name. See [`Table::field()`].
+    pub fn field(&self, name: &str) -> Result<Field, JadbError> {
+        self.table().field(name)
+    }
+    /// # disable_full_text()
+    ///
+    /// This removes the full-text index of the table. See [`Table::disable_full_text()`].
+    pub fn disable_full_text(&mut self) -> Result<(), JadbError> {
+        let db = &mut *self.db;
+        let table = Table {
+            path: path_of(&db.tables, self.id),
+            id: self.id,
+        };
+        table.disable_full_text(&mut db.hash_storage)
+    }
+    /// # migrate()
+    ///
+    /// This rewrites all rows of the table written by older versions of jadb in the current format, moves rows saved in their own files
+    /// to the segment files and compacts the table. See [`Table::migrate()`].
+    pub fn migrate(&self) -> Result<usize, JadbError> {
+        self.table().migrate(self.db.cipher.as_ref())
+    }
+    /// # compact()
+    ///
+    /// This removes replaced and deleted rows from the table's segment files. See [`Table::compact()`].
+    pub fn compact(&self) -> Result<(), JadbError> {
+        self.table().compact()
+    }
+    /// # delete_row()
+    ///
+    /// This deletes a row from the table. See [`Row::delete()`].
+    pub fn delete_row(&mut self, row: Row) -> Result<(), JadbError> {
+        let db = &mut *self.db;
+        let table = Table {
+            path: path_of(&db.tables, self.id),
+            id: self.id,
+        };
+        row.delete(table, &mut db.hash_storage)
+    }
+    /// # append_field()
+    ///
+    /// This adds a field to the end of a row of the table. See [`Row::append_field()`].
+    pub fn append_field(&mut self, row: Row, value: impl Into<Value>) -> Result<(), JadbError> {
+        let db = &mut *self.db;
+        let table = Table {
+            path: path_of(&db.tables, self.id),
+            id: self.id,
+        };
+        row.append_field(table, value, &mut db.hash_storage, db.cipher.as_ref())
+    }
+    /// # set_field()
+    ///
+    /// This replaces the value of a field of a row of the table. See [`Field::set()`].
+    pub fn set_field(
+        &mut self,
+        row: Row,
+        field: Field,
+        value: impl Into<Value>,
+    ) -> Result<(), JadbError> {
+        let db = &mut *self.db;
+        let table = Table {
+            path: path_of(&db.tables, self.id),
+            id: self.id,
+        };
+        field.set(table, row, value, &mut db.hash_storage, db.cipher.as_ref())
+    }
+    /// # insert_field()
+    ///
+    /// This inserts a field into a row of the table. See [`Field::insert()`].
+    pub fn insert_field(
+        &mut self,
+        row: Row,
+        field: Field,
+        value: impl Into<Value>,
+    ) -> Result<(), JadbError> {
+        let db = &mut *self.db;
+        let table = Table {
+            path: path_of(&db.tables, self.id),
+            id: self.id,
+        };
+        field.insert(table, row, value, &mut db.hash_storage, db.cipher.as_ref())
+    }
+    /// # delete_field()
+    ///
+    /// This deletes a field from a row of the table. See [`Field::delete()`].
+    pub fn delete_field(&mut self, row: Row, field: Field) -> Result<(), JadbError> {
+        let db = &mut *self.db;
+        let table = Table {
+            path: path_of(&db.tables, self.id),
+            id: self.id,
+        };
+        field.delete(table, row, &mut db.hash_storage, db.cipher.as_ref())
+    }
+}
+
+fn path_of(tables: &[Option<TableEntry>], id: usize) -> &str {
+    match &tables[id] {
+        Some(entry) => &entry.path,
+        None => "", // handles only exist for registered tables
+    }
+}
+
+fn check_name(name: &str) -> Result<(), JadbError> {
+    // table names are used as directory names in the database root and as lines of the catalog
+    if name.is_empty() {
+        return Err(JadbError::EmptyPath);
+    }
+    if name == "."
+        || name == ".."
+        || name.contains('/')
+        || name.contains('\\')
+        || name.contains(char::is_control)
+    {
+        return Err(JadbError::InvalidName(name.to_string()));
+    }
+    Ok(())
+}
@@ -0,0 +1,152 @@
+/// # JadbError
+///
+/// Every fallible operation of jadb returns this error type instead of a status code.
+///
+/// ## Examples
+/// ```
+/// use jadb;
+///
+/// let table = jadb::Table {
+///     path: "",
+///     id: 0,
+/// };
+///
+/// match table.create() {
+///     Err(jadb::JadbError::EmptyPath) => {} // a table needs a path
+///     _ => unreachable!(),
+/// }
+/// ```
+#[derive(Debug)]
+pub enum JadbError {
+    /// The table, row or file at the given path doesn't exist.
+    NotFound(String),
+    /// Something already exists at the given path.
+    AlreadyExists(String),
+    /// No table path was given.
+    EmptyPath,
+    /// The given table name can't be used, e.g. because it contains a path separator.
+    InvalidName(String),
+    /// No content was given to write.
+    EmptyContent,
+    /// The file system returned an error.
+    Io(std::io::Error),
+    /// The content couldn't be encrypted.
+    Encrypt,
+    /// The row at the given path couldn't be decrypted, e.g. because of a wrong key.
+    Decrypt(String),
+    /// The row at the given path was written for another table or row, e.g. because it was copied.
+    Misplaced { path: String, table: u64, row: u64 },
+    /// The row at the given path is encrypted with a key, identified by its key id, that wasn't given.
+    UnknownKey { path: String, key_id: u32 },
+    /// The table is encrypted with another algorithm than the one of the given cipher.
+    CipherMismatch {
+        expected: crate::Algorithm,
+        found: crate::Algorithm,
+    },
+    /// The passphrase doesn't match the keyfile of the database.
+    WrongPassphrase,
+    /// The database uses the keyfile at the given path, so its key can only be changed through the keyfile.
+    KeyfileInUse(String),
+    /// A key rotation of the keyfile was started but not finished, it is finished when the database is opened again.
+    RotationInProgress,
+    /// A file of the database doesn't have the expected structure.
+    Corrupt(String),
+    /// The table or row was written in a format version this version of jadb can't read.
+    UnsupportedFormat(String),
+    /// A decrypted field isn't valid UTF-8.
+    InvalidUtf8(std::str::Utf8Error),
+    /// The table at the given path has no full-text index.
+    NoFullText(String),
+    /// The search pattern isn't a valid regular expression.
+    InvalidRegex(regex::Error),
+    /// An update can't be applied to the field, e.g. because it adds a number to a text.
+    InvalidUpdate(String),
+    /// The values of a row don't match the schema of its table.
+    SchemaViolation(String),
+    /// The schema of the table has no column with the given name.
+    UnknownColumn(String),
+    /// The table was created with another id than the one it is used with.
+    TableIdMismatch { expected: usize, found: String },
+    /// A field or table position is out of range.
+    IndexOutOfRange { index: usize, len: usize },
+    /// A value is longer than the largest length that can be saved.
+    TooLong { len: usize, max: usize },
+}
+
+impl std::fmt::Display for JadbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JadbError::NotFound(path) => write!(f, "nothing found at {}", path),
+            JadbError::AlreadyExists(path) => write!(f, "already exists at {}", path),
+            JadbError::EmptyPath => write!(f, "no table path given"),
+            JadbError::InvalidName(name) => write!(f, "invalid table name {}", name),
+            JadbError::EmptyContent => write!(f, "no content given"),
+            JadbError::Io(err) => write!(f, "i/o error: {}", err),
+            JadbError::Encrypt => write!(f, "encryption failed"),
+            JadbError::Decrypt(path) => write!(f, "couldn't decrypt {}", path),
+            JadbError::Misplaced { path, table, row } => {
+                write!(f, "{} belongs to row {} of table {}", path, row, table)
+            }
+            JadbError::UnknownKey { path, key_id } => {
+                write!(f, "{} is encrypted with unknown key {:08x}", path, key_id)
+            }
+            JadbError::CipherMismatch { expected, found } => write!(
+                f,
+                "table uses cipher {} instead of {}",
+                expected.name(),
+                found.name()
+            ),
+            JadbError::WrongPassphrase => write!(f, "wrong passphrase"),
+            JadbError::KeyfileInUse(path) => write!(f, "key is kept in keyfile {}", path),
+            JadbError::RotationInProgress => write!(f, "a key rotation is already in progress"),
+            JadbError::Corrupt(reason) => write!(f, "corrupt database: {}", reason),
+            JadbError::UnsupportedFormat(version) => {
+                write!(f, "unsupported format version {}", version)
+            }
+            JadbError::InvalidUtf8(err) => write!(f, "field isn't valid utf-8: {}", err),
+            JadbError::NoFullText(path) => write!(f, "{} has no full-text index", path),
+            JadbError::InvalidRegex(err) => write!(f, "invalid regex: {}", err),
+            JadbError::InvalidUpdate(reason) => write!(f, "invalid update: {}", reason),
+            JadbError::SchemaViolation(reason) => write!(f, "schema violated: {}", reason),
+            JadbError::UnknownColumn(name) => write!(f, "no column named {}", name),
+            JadbError::TableIdMismatch { expected, found } => {
+                write!(f, "table has id {} instead of {}", found, expected)
+            }
+            JadbError::IndexOutOfRange { index, len } => {
+                write!(f, "index {} out of range for length {}", index, len)
+            }
+            JadbError::TooLong { len, max } => {
+                write!(f, "length {} is longer than the maximum {}", len, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for JadbError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JadbError::Io(err) => Some(err),
+            JadbError::InvalidUtf8(err) => Some(err),
+            JadbError::InvalidRegex(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for JadbError {
+    fn from(err: std::io::Error) -> Self {
+        JadbError::Io(err)
+    }
+}
+
+impl From<std::str::Utf8Error> for JadbError {
+    fn from(err: std::str::Utf8Error) -> Self {
+        JadbError::InvalidUtf8(err)
+    }
+}
+
+impl From<regex::Error> for JadbError {
+    fn from(err: regex::Error) -> Self {
+        JadbError::InvalidRegex(err)
+    }
+}
@@ -0,0 +1,195 @@
+// the on-disk format of jadb
+//
+// Every row starts with a header:
+//
+// | Bytes | Content |
+// | ----- | ------- |
+// | 4 | magic `JADB` |
+// | 1 | format version |
+// | 8 | table id (u64, little endian) |
+// | 8 | row position (u64, little endian) |
+// | 4 | key id (u32, little endian) |
+// | 1 | cipher, see `Algorithm::id()` |
+// | 0, 12 or 24 | nonce, as long as the cipher needs |
+//
+// followed by the ciphertext of the row, whose plaintext are the encoded values of the row, see `value.rs`. The whole header is the associated data of the ciphertext,
+// so a row can't be moved to another row or table. The key id tells which key the row is encrypted with,
+// so a table can be read while its key is rotated and only some rows are re-encrypted yet.
+// Format version 1 had no table id and row position, format version 2 had no key id and format version 3 had no cipher.
+// Up to format version 4, rows contained text fields separated by `\n` instead of encoded values.
+// Rows of these versions are encrypted with AES-128-GCM and have a 12 byte nonce.
+// Up to format version 5, every row was saved in its own file named by its position, since format version 6 rows are saved in segments, see `segment.rs`.
+// The info file of a table contains the format version in its `format version` line.
+//
+// Content hashes and the checksums of segment records are SipHash-2-4 with the fixed keys below, so they don't change between Rust releases.
+// The fingerprint of a row is the checksum of its record, which contains the random nonce, so every write gives a new fingerprint,
+// which can be checked without decrypting the row. Fingerprints of row files of older versions hash the length, the start and the end of the file.
+
+use std::hash::Hasher;
+use std::io::{Read, Seek, SeekFrom};
+
+use siphasher::sip::SipHasher24;
+
+use crate::{Algorithm, JadbError};
+
+/// First bytes of every row.
+pub(crate) const MAGIC: &[u8; 4] = b"JADB";
+
+/// Version of the on-disk format written by this version of jadb.
+pub(crate) const FORMAT_VERSION: u8 = 6;
+
+// length of the row header without the nonce
+const FIXED_HEADER_LEN: usize = MAGIC.len() + 1 + 8 + 8 + 4 + 1;
+
+// length of the nonce in format versions before 4
+const OLD_NONCE_LEN: usize = 12;
+
+// number of bytes at the start and end of an old row file that are part of its fingerprint
+const FINGERPRINT_HEAD: usize = 64;
+const FINGERPRINT_TAIL: usize = 16;
+
+// keys of the content hash, "jadb content hsh" as two little endian numbers
+const HASH_KEY_0: u64 = u64::from_le_bytes(*b"jadb con");
+const HASH_KEY_1: u64 = u64::from_le_bytes(*b"tent hsh");
+
+/// Builds the header of a row. The nonce must have the length of the algorithm's nonces.
+pub(crate) fn row_header(
+    table_id: usize,
+    pos: usize,
+    key_id: u32,
+    algorithm: Algorithm,
+    nonce: &[u8],
+) -> Vec<u8> {
+    let mut header = Vec::with_capacity(FIXED_HEADER_LEN + nonce.len());
+    header.extend_from_slice(MAGIC);
+    header.push(FORMAT_VERSION);
+    header.extend_from_slice(&(table_id as u64).to_le_bytes());
+    header.extend_from_slice(&(pos as u64).to_le_bytes());
+    header.extend_from_slice(&key_id.to_le_bytes());
+    header.push(algorithm.id());
+    header.extend_from_slice(nonce);
+    header
+}
+
+/// The parts of a row.
+pub(crate) struct RowFile<'a> {
+    pub(crate) version: u8,
+    pub(crate) table_id: u64, // table and row it was written for, 0 in version 1
+    pub(crate) pos: u64,
+    pub(crate) key_id: Option<u32>, // key the row is encrypted with, unknown before version 3
+    pub(crate) algorithm: Algorithm, // always AES-128-GCM before version 4
+    pub(crate) aad: &'a [u8],       // associated data of the ciphertext, empty in version 1
+    pub(crate) nonce: &'a [u8],
+    pub(crate) ciphertext: &'a [u8],
+}
+
+/// Splits a row into its header fields and ciphertext.
+///
+/// Returns `None` if the row doesn't start with a header, e.g. because it was written by an older version of jadb.
+/// Returns `JadbError::UnsupportedFormat` if it was written by a newer version of jadb.
+pub(crate) fn split_row(content: &[u8]) -> Result<Option<RowFile<'_>>, JadbError> {
+    let v1_len = MAGIC.len() + 1 + OLD_NONCE_LEN;
+    if content.len() < v1_len || &content[..MAGIC.len()] != MAGIC {
+        return Ok(None);
+    }
+    let number = |at: usize| {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&content[at..at + 8]);
+        u64::from_le_bytes(bytes)
+    };
+    let key_id = || {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&content[MAGIC.len() + 17..MAGIC.len() + 21]);
+        u32::from_le_bytes(bytes)
+    };
+    let (version, header_len, algorithm) = match content[MAGIC.len()] {
+        1 => (1, v1_len, Algorithm::Aes128Gcm),
+        2 => (2, MAGIC.len() + 17 + OLD_NONCE_LEN, Algorithm::Aes128Gcm),
+        3 => (3, MAGIC.len() + 21 + OLD_NONCE_LEN, Algorithm::Aes128Gcm),
+        version @ 4..=FORMAT_VERSION if content.len() >= FIXED_HEADER_LEN => {
+            let id = content[FIXED_HEADER_LEN - 1];
+            let algorithm = Algorithm::from_id(id)
+                .ok_or_else(|| JadbError::UnsupportedFormat(format!("cipher {}", id)))?;
+            (version, FIXED_HEADER_LEN + algorithm.nonce_len(), algorithm)
+        }
+        4..=FORMAT_VERSION => return Ok(None),
+        version => return Err(JadbError::UnsupportedFormat(version.to_string())),
+    };
+    if content.len() < header_len {
+        return Ok(None); // too short for a header, so it has to be an older row without one
+    }
+    let (header, ciphertext) = content.split_at(header_len);
+    let nonce = &header[header_len - algorithm.nonce_len()..];
+    if version == 1 {
+        return Ok(Some(RowFile {
+            version,
+            table_id: 0,
+            pos: 0,
+            key_id: None,
+            algorithm,
+            aad: &[],
+            nonce,
+            ciphertext,
+        }));
+    }
+    Ok(Some(RowFile {
+        version,
+        table_id: number(MAGIC.len() + 1),
+        pos: number(MAGIC.len() + 9),
+        key_id: if version >= 3 { Some(key_id()) } else { None },
+        algorithm,
+        aad: header,
+        nonce,
+        ciphertext,
+    }))
+}
+
+/// Checks the format version of a table's info file.
+///
+/// Tables of older versions, or without a version, can still be read.
+pub(crate) fn check_version(version: Option<String>) -> Result<(), JadbError> {
+    match version {
+        Some(version) if version.parse::<u8>().map_or(true, |v| v > FORMAT_VERSION) => {
+            Err(JadbError::UnsupportedFormat(version))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Hashes the fields of a row. Every field is hashed as its length in bytes (u64, little endian) followed by its bytes.
+pub(crate) fn hash_fields<S: AsRef<str>>(fields: &[S]) -> u64 {
+    let mut hasher = SipHasher24::new_with_keys(HASH_KEY_0, HASH_KEY_1);
+    for field in fields {
+        let field = field.as_ref().as_bytes();
+        hasher.write(&(field.len() as u64).to_le_bytes());
+        hasher.write(field);
+    }
+    hasher.finish()
+}
+
+/// Returns the checksum of a segment record.
+pub(crate) fn checksum(content: &[u8]) -> u64 {
+    let mut hasher = SipHasher24::new_with_keys(HASH_KEY_0, HASH_KEY_1);
+    hasher.write(content);
+    hasher.finish()
+}
+
+/// Returns the fingerprint of the row file of an older version at the given path, reading only its start and end.
+pub(crate) fn file_fingerprint(path: &str) -> Result<u64, JadbError> {
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut head = vec![0u8; (len as usize).min(FINGERPRINT_HEAD)];
+    file.read_exact(&mut head)?;
+    let mut tail = vec![0u8; (len as usize).min(FINGERPRINT_TAIL)];
+    file.seek(SeekFrom::End(-(tail.len() as i64)))?;
+    file.read_exact(&mut tail)?;
+    Ok(fingerprint_parts(len, &head, &tail))
+}
+
+fn fingerprint_parts(len: u64, head: &[u8], tail: &[u8]) -> u64 {
+    let mut hasher = SipHasher24::new_with_keys(HASH_KEY_0, HASH_KEY_1);
+    hasher.write(&len.to_le_bytes());
+    hasher.write(head);
+    hasher.write(tail);
+    hasher.finish()
+}
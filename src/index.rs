@@ -0,0 +1,499 @@
+// the in-memory index of all field contents
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::{Bound, RangeBounds};
+
+use regex::Regex;
+use regex_syntax::hir::{Hir, HirKind, Literal};
+
+use crate::text::TextIndex;
+use crate::{Field, FullText, Hit, IndexOptions, JadbError, Row, TextHit, Value};
+
+/// # HashStorage
+///
+/// The hash storage is an inverted index of the contents of every field: it maps every value, in its text form, to the list of fields it is saved in,
+/// so a value is found without looking at any row. A value saved in several fields of the same row is found in all of them.
+///
+/// The values are kept in order, so all values starting with a prefix are next to each other. For substrings and regular expressions,
+/// every value is indexed by its trigrams, the sequences of three characters it contains, so only values containing all trigrams
+/// of the searched text have to be compared with it. Fuzzy searches walk the ordered values once, sharing the edit distance computation
+/// between values with a common prefix and skipping all values with a prefix that is already too far from the term.
+///
+/// Values are indexed as normalized by the [`IndexOptions`] of their table, and searches normalize the term the same way.
+///
+/// Numbers and timestamps are also kept in order for every field of every table, so [`Table::range()`](crate::Table::range()) finds them without a scan.
+///
+/// Tables with a full-text index, see [`Table::enable_full_text()`](crate::Table::enable_full_text()), also have the words of their fields indexed.
+///
+/// It is kept up to date by [`init()`](crate::init()), [`Table::write()`](crate::Table::write()), [`Row::delete()`](crate::Row::delete())
+/// and [`Field::delete()`](crate::Field::delete()).
+///
+/// ## Examples
+/// ```
+/// let hash_storage = jadb::HashStorage::new();
+///
+/// assert!(hash_storage.get("hi").is_empty());
+/// ```
+#[derive(Default, Clone, Debug)]
+pub struct HashStorage {
+    postings: BTreeMap<String, BTreeSet<Hit>>, // fields containing each value, ordered by table, row and field
+    trigrams: HashMap<[char; 3], BTreeSet<String>>, // values containing each trigram
+    text: BTreeMap<usize, TextIndex>,          // full-text indexes by table id
+    options: BTreeMap<usize, IndexOptions>, // index options by table id, if they aren't the default
+    ordered: BTreeMap<(usize, usize), BTreeSet<(Value, Row)>>, // numbers and timestamps of each field by table id and field position
+    rows: BTreeMap<(usize, usize), Vec<Value>>, // values of each row by table id and row position, to remove them again
+}
+
+impl HashStorage {
+    /// Creates an empty hash storage.
+    pub fn new() -> HashStorage {
+        HashStorage::default()
+    }
+    /// Returns every field containing exactly the value, ordered by table, row and field.
+    pub fn get(&self, value: &str) -> Vec<Hit> {
+        self.find(None, |options| Ok(self.key(&options.normalize(value))))
+            .unwrap_or_default()
+    }
+    /// Returns every field of the given table containing exactly the value, ordered by row and field.
+    pub fn get_in_table(&self, table: usize, value: &str) -> Vec<Hit> {
+        self.find(Some(table), |options| {
+            Ok(self.key(&options.normalize(value)))
+        })
+        .unwrap_or_default()
+    }
+    /// Returns the index options of the given table.
+    pub fn index_options(&self, table: usize) -> IndexOptions {
+        self.options.get(&table).copied().unwrap_or_default()
+    }
+    /// Returns every indexed value starting with the prefix, in order.
+    ///
+    /// The values are returned as indexed, so e.g. lowercased for tables with case folding.
+    pub fn values_with_prefix(&self, prefix: &str) -> Vec<&str> {
+        self.postings
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .map(|(value, _)| value.as_str())
+            .take_while(|value| value.starts_with(prefix))
+            .collect()
+    }
+    /// Returns every indexed value containing the text, in order.
+    pub fn values_containing(&self, text: &str) -> Vec<&str> {
+        self.candidates(&[text])
+            .into_iter()
+            .filter(|value| value.contains(text))
+            .collect()
+    }
+    /// Returns every indexed value matching the regular expression, in order.
+    ///
+    /// Returns `JadbError::InvalidRegex` if the pattern isn't a valid regular expression.
+    pub fn values_matching(&self, pattern: &str) -> Result<Vec<&str>, JadbError> {
+        let regex = Regex::new(pattern)?;
+        let mut literals = vec![];
+        if let Ok(hir) = regex_syntax::parse(pattern) {
+            required_literals(&hir, &mut literals);
+        }
+        let literals: Vec<&str> = literals.iter().map(String::as_str).collect();
+        Ok(self
+            .candidates(&literals)
+            .into_iter()
+            .filter(|value| regex.is_match(value))
+            .collect())
+    }
+    /// Returns every indexed value within the Levenshtein distance of the term and its distance, in order.
+    pub fn values_within(&self, term: &str, max_distance: usize) -> Vec<(&str, usize)> {
+        let term: Vec<char> = term.chars().collect();
+        let mut found = vec![];
+        let mut rows: Vec<Vec<usize>> = vec![(0..=term.len()).collect()]; // distances of the term's prefixes to each prefix of `prefix`
+        let mut prefix: Vec<char> = vec![];
+        let mut start = Bound::Unbounded;
+        while let Some((value, _)) = self
+            .postings
+            .range::<str, _>((start.as_ref().map(String::as_str), Bound::Unbounded))
+            .next()
+        {
+            let chars: Vec<char> = value.chars().collect();
+            let common = prefix
+                .iter()
+                .zip(&chars)
+                .take_while(|(a, b)| a == b)
+                .count();
+            rows.truncate(common + 1); // the rows of the common prefix stay valid
+            prefix.truncate(common);
+            start = Bound::Excluded(value.clone());
+            for &c in &chars[common..] {
+                let last = &rows[rows.len() - 1];
+                let mut row = vec![last[0] + 1];
+                for (i, &t) in term.iter().enumerate() {
+                    let substitution = last[i] + usize::from(t != c);
+                    row.push(substitution.min(last[i + 1] + 1).min(row[i] + 1));
+                }
+                let dead = row.iter().all(|&distance| distance > max_distance);
+                rows.push(row);
+                prefix.push(c);
+                if dead {
+                    // no value with this prefix can be close enough, continue after all of them
+                    start = match successor(&prefix) {
+                        Some(successor) => Bound::Included(successor),
+                        None => return found,
+                    };
+                    break;
+                }
+            }
+            let distance = rows[rows.len() - 1][term.len()];
+            if prefix.len() == chars.len() && distance <= max_distance {
+                found.push((value.as_str(), distance));
+            }
+        }
+        found
+    }
+    /// Returns the full-text options of the given table, if it has a full-text index.
+    pub fn full_text(&self, table: usize) -> Option<FullText> {
+        self.text.get(&table).map(|index| index.options)
+    }
+    /// Returns every field matching any word of the query, ranked by BM25 score with the best match first.
+    /// If a table is given, only its fields are searched.
+    pub(crate) fn search_text(&self, query: &str, table: Option<usize>) -> Vec<TextHit> {
+        let mut text_hits = vec![];
+        for (&id, index) in &self.text {
+            if table.is_some() && table != Some(id) {
+                continue;
+            }
+            let mut terms = index.options.tokens(query);
+            terms.sort_unstable();
+            terms.dedup(); // every word of the query counts once
+            for ((row, field), score) in index.scores(&terms) {
+                let value = self.rows[&(id, row)][field].to_string();
+                text_hits.push(TextHit {
+                    hit: Hit {
+                        table: id,
+                        row: Row { pos: row },
+                        field: Field { pos: field },
+                    },
+                    score,
+                    highlights: index
+                        .options
+                        .token_ranges(&value)
+                        .into_iter()
+                        .filter(|(_, term)| terms.binary_search(term).is_ok())
+                        .map(|(range, _)| range)
+                        .collect(),
+                });
+            }
+        }
+        text_hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.hit.cmp(&b.hit)));
+        text_hits
+    }
+    /// Adds a full-text index with the given options to the table, or removes it.
+    pub(crate) fn set_full_text(&mut self, table: usize, options: Option<FullText>) {
+        let options = match options {
+            Some(options) => options,
+            None => {
+                self.text.remove(&table);
+                return;
+            }
+        };
+        let mut index = TextIndex::new(options);
+        for (&(_, row), fields) in self.rows.range((table, 0)..(table + 1, 0)) {
+            for (field, value) in fields.iter().enumerate() {
+                index.add_field(row, field, &value.to_string());
+            }
+        }
+        self.text.insert(table, index);
+    }
+    /// Changes the index options of the table and indexes its values again.
+    pub(crate) fn set_index_options(&mut self, table: usize, options: IndexOptions) {
+        let rows: Vec<(usize, Vec<Value>)> = self
+            .rows
+            .range((table, 0)..(table + 1, 0))
+            .map(|(&(_, pos), fields)| (pos, fields.clone()))
+            .collect();
+        for (pos, _) in &rows {
+            self.remove_row(table, Row { pos: *pos }); // with the old options
+        }
+        match options == IndexOptions::default() {
+            true => self.options.remove(&table),
+            false => self.options.insert(table, options),
+        };
+        for (pos, fields) in rows {
+            self.set_row(table, Row { pos }, fields);
+        }
+    }
+    /// Returns the hits of the values found by `values`, ordered by table, row and field. If a table is given, only its hits are returned.
+    ///
+    /// `values` is called once for every set of index options the searched tables use, and has to normalize the search term with them.
+    pub(crate) fn find<'s>(
+        &'s self,
+        table: Option<usize>,
+        values: impl Fn(IndexOptions) -> Result<Vec<&'s str>, JadbError>,
+    ) -> Result<Vec<Hit>, JadbError> {
+        let all_options = match table {
+            Some(table) => vec![self.index_options(table)],
+            None => {
+                let mut all_options = vec![IndexOptions::default()];
+                for options in self.options.values() {
+                    if !all_options.contains(options) {
+                        all_options.push(*options);
+                    }
+                }
+                all_options
+            }
+        };
+        let mut hits = vec![];
+        for options in all_options {
+            for value in values(options)? {
+                let postings = &self.postings[value];
+                let found = match table {
+                    Some(table) => postings.range(first_hit(table)..first_hit(table + 1)),
+                    None => postings.range(..),
+                };
+                // the same value may stand for other values in tables with other options
+                hits.extend(found.filter(|hit| self.index_options(hit.table) == options));
+            }
+        }
+        hits.sort_unstable();
+        Ok(hits)
+    }
+    /// Returns the values of every row of the table, ordered by position.
+    pub(crate) fn table_rows(&self, table: usize) -> impl Iterator<Item = (Row, &[Value])> {
+        self.rows
+            .range((table, 0)..(table + 1, 0))
+            .map(|(&(_, pos), fields)| (Row { pos }, fields.as_slice()))
+    }
+    /// Returns every field at the position of the given table with a number or timestamp in the range, ordered by value and row.
+    ///
+    /// If only one end of the range is given, the other one is the first or last value of the same type.
+    pub(crate) fn range(
+        &self,
+        table: usize,
+        field: Field,
+        range: impl RangeBounds<Value>,
+    ) -> Vec<Hit> {
+        let values = match self.ordered.get(&(table, field.pos)) {
+            Some(values) => values,
+            None => return vec![],
+        };
+        let first = Row { pos: 0 };
+        let last = Row { pos: usize::MAX };
+        let start = match (range.start_bound(), range.end_bound()) {
+            (Bound::Included(start), _) => Bound::Included((start.clone(), first)),
+            (Bound::Excluded(start), _) => Bound::Excluded((start.clone(), last)),
+            (Bound::Unbounded, Bound::Included(end) | Bound::Excluded(end)) => {
+                Bound::Included((end.first_of_kind(), first))
+            }
+            (Bound::Unbounded, Bound::Unbounded) => Bound::Unbounded,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => Bound::Included((end.clone(), last)),
+            Bound::Excluded(end) => Bound::Excluded((end.clone(), first)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        match (&start, &end) {
+            (
+                Bound::Included(start) | Bound::Excluded(start),
+                Bound::Included(end) | Bound::Excluded(end),
+            ) if start > end => return vec![], // empty, BTreeSet::range would panic
+            (Bound::Excluded(start), Bound::Excluded(end)) if start == end => return vec![],
+            _ => {}
+        }
+        let open_end = match (range.start_bound(), range.end_bound()) {
+            (Bound::Included(start) | Bound::Excluded(start), Bound::Unbounded) => Some(start),
+            _ => None,
+        };
+        values
+            .range((start, end))
+            .take_while(|(value, _)| open_end.is_none_or(|start| value.same_kind(start)))
+            .map(|&(_, row)| Hit { table, row, field })
+            .collect()
+    }
+    /// Replaces the values of a row.
+    pub(crate) fn set_row(&mut self, table: usize, row: Row, fields: Vec<Value>) {
+        self.remove_row(table, row);
+        let options = self.index_options(table);
+        for (pos, value) in fields.iter().enumerate() {
+            let value = options.normalize(&value.to_string());
+            if !self.postings.contains_key(&value) {
+                for trigram in trigrams(&value) {
+                    self.trigrams
+                        .entry(trigram)
+                        .or_default()
+                        .insert(value.clone());
+                }
+            }
+            self.postings.entry(value).or_default().insert(Hit {
+                table,
+                row,
+                field: Field { pos },
+            });
+        }
+        if let Some(index) = self.text.get_mut(&table) {
+            for (pos, value) in fields.iter().enumerate() {
+                index.add_field(row.pos, pos, &value.to_string());
+            }
+        }
+        for (pos, value) in fields
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| is_ordered(value))
+        {
+            self.ordered
+                .entry((table, pos))
+                .or_default()
+                .insert((value.clone(), row));
+        }
+        self.rows.insert((table, row.pos), fields);
+    }
+    /// Removes the values of a row.
+    pub(crate) fn remove_row(&mut self, table: usize, row: Row) {
+        if let Some(fields) = self.rows.remove(&(table, row.pos)) {
+            self.remove_postings(table, row, &fields);
+            if let Some(index) = self.text.get_mut(&table) {
+                for (pos, value) in fields.iter().enumerate() {
+                    index.remove_field(row.pos, pos, &value.to_string());
+                }
+            }
+            for (pos, value) in fields.into_iter().enumerate() {
+                if let Some(values) = self.ordered.get_mut(&(table, pos)) {
+                    values.remove(&(value, row));
+                    if values.is_empty() {
+                        self.ordered.remove(&(table, pos));
+                    }
+                }
+            }
+        }
+    }
+    /// Removes the values of all rows of a table, its full-text index and its index options.
+    pub(crate) fn remove_table(&mut self, table: usize) {
+        self.text.remove(&table);
+        let rows: Vec<(usize, usize)> = self
+            .rows
+            .range((table, 0)..(table + 1, 0))
+            .map(|(&key, _)| key)
+            .collect();
+        for (table, pos) in rows {
+            self.remove_row(table, Row { pos });
+        }
+        self.options.remove(&table); // after the values, which are normalized with them
+    }
+    fn remove_postings(&mut self, table: usize, row: Row, fields: &[Value]) {
+        let options = self.index_options(table);
+        for (pos, value) in fields.iter().enumerate() {
+            let value = &options.normalize(&value.to_string());
+            if let Some(hits) = self.postings.get_mut(value) {
+                hits.remove(&Hit {
+                    table,
+                    row,
+                    field: Field { pos },
+                });
+                if hits.is_empty() {
+                    self.postings.remove(value); // don't keep values which aren't saved anymore
+                    self.remove_trigrams(value);
+                }
+            }
+        }
+    }
+    fn key(&self, value: &str) -> Vec<&str> {
+        // the indexed value equal to the given one, if there is one
+        self.postings
+            .get_key_value(value)
+            .map(|(key, _)| key.as_str())
+            .into_iter()
+            .collect()
+    }
+    fn remove_trigrams(&mut self, value: &str) {
+        for trigram in trigrams(value) {
+            if let Some(values) = self.trigrams.get_mut(&trigram) {
+                values.remove(value);
+                if values.is_empty() {
+                    self.trigrams.remove(&trigram);
+                }
+            }
+        }
+    }
+    fn candidates(&self, texts: &[&str]) -> Vec<&str> {
+        // values containing every trigram of the texts, or all values if the texts are too short to have any
+        let mut candidates: Option<BTreeSet<&str>> = None;
+        for trigram in texts.iter().flat_map(|text| trigrams(text)) {
+            let values: BTreeSet<&str> = match self.trigrams.get(&trigram) {
+                Some(values) => values.iter().map(String::as_str).collect(),
+                None => return vec![], // no value contains the trigram
+            };
+            candidates = Some(match candidates {
+                Some(candidates) => candidates.intersection(&values).copied().collect(),
+                None => values,
+            });
+        }
+        match candidates {
+            Some(candidates) => candidates.into_iter().collect(),
+            None => self.postings.keys().map(String::as_str).collect(),
+        }
+    }
+}
+
+fn trigrams(value: &str) -> Vec<[char; 3]> {
+    let chars: Vec<char> = value.chars().collect();
+    chars
+        .windows(3)
+        .map(|window| [window[0], window[1], window[2]])
+        .collect()
+}
+
+fn required_literals(hir: &Hir, literals: &mut Vec<String>) {
+    // collect texts every match of the regular expression must contain
+    match hir.kind() {
+        HirKind::Literal(Literal(bytes)) => {
+            if let Ok(text) = std::str::from_utf8(bytes) {
+                literals.push(text.to_string());
+            }
+        }
+        HirKind::Capture(capture) => required_literals(&capture.sub, literals),
+        HirKind::Repetition(repetition) if repetition.min > 0 => {
+            required_literals(&repetition.sub, literals)
+        }
+        HirKind::Concat(subs) => {
+            let mut run = String::new(); // adjacent literals form one longer text
+            for sub in subs {
+                match sub.kind() {
+                    HirKind::Literal(Literal(bytes)) => match std::str::from_utf8(bytes) {
+                        Ok(text) => run.push_str(text),
+                        Err(_) => literals.push(std::mem::take(&mut run)),
+                    },
+                    _ => {
+                        literals.push(std::mem::take(&mut run));
+                        required_literals(sub, literals);
+                    }
+                }
+            }
+            literals.push(run);
+        }
+        _ => {} // e.g. alternations and classes don't require a single text
+    }
+}
+
+fn successor(prefix: &[char]) -> Option<String> {
+    // the smallest string larger than every string with the prefix
+    let mut prefix = prefix.to_vec();
+    while let Some(last) = prefix.pop() {
+        let next = match last as u32 + 1 {
+            0xD800 => Some('\u{E000}'), // skip the surrogates, which aren't chars
+            next => char::from_u32(next),
+        };
+        if let Some(next) = next {
+            prefix.push(next);
+            return Some(prefix.into_iter().collect());
+        }
+    }
+    None
+}
+
+fn is_ordered(value: &Value) -> bool {
+    // the values ranges can be queried for, texts are ordered in the postings already
+    matches!(value, Value::Int(_) | Value::Float(_) | Value::Timestamp(_))
+}
+
+fn first_hit(table: usize) -> Hit {
+    // smallest hit of the table, for ranges over the postings
+    Hit {
+        table,
+        row: Row { pos: 0 },
+        field: Field { pos: 0 },
+    }
+}
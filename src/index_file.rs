@@ -0,0 +1,181 @@
+// the index file of a table, so init doesn't have to decrypt every row
+//
+// The contents of all rows of a table are saved in `index.jadb` in the table directory. The file starts with a header:
+//
+// | Bytes | Content |
+// | ----- | ------- |
+// | 4 | magic `JADX` |
+// | 1 | index format version |
+// | 8 | table id (u64, little endian) |
+// | 1 | cipher, see `Algorithm::id()` |
+// | 4 | key id (u32, little endian) |
+//
+// followed by records, which are appended on every write:
+//
+// | Bytes | Content |
+// | ----- | ------- |
+// | 4 | length of nonce and ciphertext (u32, little endian) |
+// | 0, 12 or 24 | nonce |
+// | n | ciphertext of the record, with the header as associated data |
+//
+// A record contains the row position (u64), the fingerprint of the row (u64) and the number of fields (u32), all little endian,
+// followed by the encoded value of every field, see `value.rs`. Version 1 saved every field as its length (u32) and text. A later record of a row replaces the earlier ones.
+// If the header doesn't match the table and cipher or a record can't be decrypted, the index is rebuilt from the rows.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use rand::RngCore;
+
+use crate::{JadbError, RowCipher, Table, Value};
+
+/// Name of the index file in the table directory.
+pub(crate) const INDEX_NAME: &str = "index.jadb";
+
+const MAGIC: &[u8; 4] = b"JADX";
+const INDEX_VERSION: u8 = 2;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 8 + 1 + 4;
+
+/// The saved contents of a row.
+pub(crate) struct Entry {
+    pub(crate) fingerprint: u64, // of the row the fields were read from
+    pub(crate) fields: Vec<Value>,
+}
+
+/// The entries of an index file by row position, and the number of records it has.
+pub(crate) struct Loaded {
+    pub(crate) entries: BTreeMap<usize, Entry>,
+    pub(crate) records: usize,
+}
+
+/// Reads the index file of the table. Returns `None` if there is none or it can't be used with the cipher.
+pub(crate) fn load(table: Table, cipher: &dyn RowCipher) -> Option<Loaded> {
+    let content = std::fs::read(path(table)).ok()?;
+    let header = header(table, cipher);
+    if content.len() < HEADER_LEN || content[..HEADER_LEN] != header[..] {
+        return None; // written for another table or key
+    }
+    let mut loaded = Loaded {
+        entries: BTreeMap::new(),
+        records: 0,
+    };
+    let nonce_len = cipher.algorithm().nonce_len();
+    let mut rest = &content[HEADER_LEN..];
+    while rest.len() >= 4 {
+        let len = u32::from_le_bytes(rest[..4].try_into().ok()?) as usize;
+        if rest.len() < 4 + len || len < nonce_len {
+            break; // the last record wasn't finished, e.g. because of a crash
+        }
+        let (nonce, con_enc) = rest[4..4 + len].split_at(nonce_len);
+        let record = cipher.decrypt(nonce, con_enc, &header)?;
+        let (pos, entry) = decode(&record)?;
+        loaded.entries.insert(pos, entry);
+        loaded.records += 1;
+        rest = &rest[4 + len..];
+    }
+    Some(loaded)
+}
+
+/// Appends the contents of a row to the index file of the table.
+pub(crate) fn append(
+    table: Table,
+    cipher: &dyn RowCipher,
+    pos: usize,
+    entry: &Entry,
+) -> Result<(), JadbError> {
+    let header = header(table, cipher);
+    let path = path(table);
+    let mut existing = [0u8; HEADER_LEN];
+    let usable = std::fs::File::open(&path)
+        .and_then(|mut file| std::io::Read::read_exact(&mut file, &mut existing))
+        .is_ok()
+        && existing == header[..];
+    let mut file = if usable {
+        std::fs::OpenOptions::new().append(true).open(&path)?
+    } else {
+        // start a new index, the missing rows are added by the next init
+        let mut file = std::fs::File::create(&path)?;
+        file.write_all(&header)?;
+        file
+    };
+    file.write_all(&record(cipher, &header, pos, entry)?)?;
+    Ok(())
+}
+
+/// Replaces the index file of the table with one containing only the given entries.
+pub(crate) fn save(
+    table: Table,
+    cipher: &dyn RowCipher,
+    entries: &BTreeMap<usize, Entry>,
+) -> Result<(), JadbError> {
+    let header = header(table, cipher);
+    let mut content = header.clone();
+    for (&pos, entry) in entries {
+        content.extend_from_slice(&record(cipher, &header, pos, entry)?);
+    }
+    crate::replace_file(&path(table), &content) // replace the old index at once
+}
+
+fn path(table: Table) -> String {
+    format!("{}/{}", table.path, INDEX_NAME)
+}
+
+fn header(table: Table, cipher: &dyn RowCipher) -> Vec<u8> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(MAGIC);
+    header.push(INDEX_VERSION);
+    header.extend_from_slice(&(table.id as u64).to_le_bytes());
+    header.push(cipher.algorithm().id());
+    header.extend_from_slice(&cipher.key_id().to_le_bytes());
+    header
+}
+
+fn record(
+    cipher: &dyn RowCipher,
+    header: &[u8],
+    pos: usize,
+    entry: &Entry,
+) -> Result<Vec<u8>, JadbError> {
+    let mut plaintext = vec![];
+    plaintext.extend_from_slice(&(pos as u64).to_le_bytes());
+    plaintext.extend_from_slice(&entry.fingerprint.to_le_bytes());
+    plaintext.extend_from_slice(&(entry.fields.len() as u32).to_le_bytes());
+    for field in &entry.fields {
+        field.encode(&mut plaintext)?;
+    }
+    let mut nonce = vec![0u8; cipher.algorithm().nonce_len()];
+    rand::rngs::OsRng.fill_bytes(&mut nonce);
+    let con_enc = cipher.encrypt(&nonce, &plaintext, header)?;
+    let mut record = ((nonce.len() + con_enc.len()) as u32)
+        .to_le_bytes()
+        .to_vec();
+    record.extend_from_slice(&nonce);
+    record.extend_from_slice(&con_enc);
+    Ok(record)
+}
+
+fn decode(record: &[u8]) -> Option<(usize, Entry)> {
+    let mut rest = record;
+    let mut take = |len: usize| {
+        if rest.len() < len {
+            return None;
+        }
+        let (taken, remaining) = rest.split_at(len);
+        rest = remaining;
+        Some(taken)
+    };
+    let pos = u64::from_le_bytes(take(8)?.try_into().ok()?) as usize;
+    let fingerprint = u64::from_le_bytes(take(8)?.try_into().ok()?);
+    let count = u32::from_le_bytes(take(4)?.try_into().ok()?);
+    let mut fields = vec![];
+    for _ in 0..count {
+        fields.push(Value::decode(&mut rest)?);
+    }
+    Some((
+        pos,
+        Entry {
+            fingerprint,
+            fields,
+        },
+    ))
+}
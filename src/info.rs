@@ -0,0 +1,132 @@
+// reading and updating the info file of a table
+//
+// The info file is replaced by a new one instead of being rewritten in place, so a crash can't leave it half written.
+// Counters like the next row position are reserved in blocks, so the file isn't replaced every time one is taken.
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, PoisonError};
+
+use crate::JadbError;
+
+/// Number of positions reserved in the info file at once by `take()`.
+const BLOCK_LEN: usize = 64;
+
+// the next value and the end of the reserved block of the counters used by this process, by table path and key
+static COUNTERS: Mutex<BTreeMap<String, (usize, usize)>> = Mutex::new(BTreeMap::new());
+
+/// Returns the `key: value` lines of the info file in the given table directory.
+pub(crate) fn read(table_path: &str) -> Result<Vec<(String, String)>, JadbError> {
+    let info_path = format!("{}/{}", table_path, "info.jadb");
+    let content = std::fs::read_to_string(&info_path).map_err(|err| match err.kind() {
+        std::io::ErrorKind::NotFound => JadbError::NotFound(info_path.clone()),
+        _ => JadbError::Io(err),
+    })?;
+    Ok(content
+        .lines()
+        .skip(1) // header line
+        .filter_map(|line| line.split_once(": "))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect())
+}
+
+/// Returns the value of the given key in the info file, if it is set.
+pub(crate) fn value(table_path: &str, key: &str) -> Result<Option<String>, JadbError> {
+    Ok(read(table_path)?
+        .into_iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value))
+}
+
+/// Sets the given key in the info file, replacing its old value.
+pub(crate) fn set(table_path: &str, key: &str, value: &str) -> Result<(), JadbError> {
+    let info_path = format!("{}/{}", table_path, "info.jadb");
+    let content = std::fs::read_to_string(&info_path)?;
+    let mut lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
+    let new_line = format!("{}: {}", key, value);
+    match lines
+        .iter()
+        .skip(1)
+        .position(|line| line.split_once(": ").map(|(k, _)| k) == Some(key))
+    {
+        Some(i) => lines[i + 1] = new_line,
+        None => lines.push(new_line),
+    }
+    crate::replace_file(&info_path, lines.join("\n").as_bytes())
+}
+
+/// Removes the given key from the info file, if it is set.
+pub(crate) fn remove(table_path: &str, key: &str) -> Result<(), JadbError> {
+    let info_path = format!("{}/{}", table_path, "info.jadb");
+    let content = std::fs::read_to_string(&info_path)?;
+    let lines: Vec<&str> = content
+        .lines()
+        .enumerate()
+        .filter(|(i, line)| *i == 0 || line.split_once(": ").map(|(k, _)| k) != Some(key))
+        .map(|(_, line)| line)
+        .collect();
+    crate::replace_file(&info_path, lines.join("\n").as_bytes())
+}
+
+/// Takes the next value of the counter with the given key. `first` returns the first value if the counter isn't set yet.
+///
+/// Values are reserved in blocks of `BLOCK_LEN` by setting the key to the end of the block, so values reserved by this process
+/// but not taken before it ends are skipped. The block is dropped if the key was changed since, e.g. by another process.
+pub(crate) fn take(
+    table_path: &str,
+    key: &str,
+    first: impl FnOnce() -> Result<usize, JadbError>,
+) -> Result<usize, JadbError> {
+    let mut counters = COUNTERS.lock().unwrap_or_else(PoisonError::into_inner);
+    let saved = counter(table_path, key)?;
+    let id = format!("{}/{}", table_path, key);
+    if let Some((next, end)) = counters.get_mut(&id) {
+        if Some(*end) == saved && next < end {
+            *next += 1;
+            return Ok(*next - 1);
+        }
+    }
+    let next = match saved {
+        Some(next) => next,
+        None => first()?,
+    };
+    set(table_path, key, &(next + BLOCK_LEN).to_string())?;
+    counters.insert(id, (next + 1, next + BLOCK_LEN));
+    Ok(next)
+}
+
+/// Makes sure the counter with the given key doesn't return `value` or a smaller value any more, see `take()`.
+pub(crate) fn skip(
+    table_path: &str,
+    key: &str,
+    value: usize,
+    first: impl FnOnce() -> Result<usize, JadbError>,
+) -> Result<(), JadbError> {
+    let mut counters = COUNTERS.lock().unwrap_or_else(PoisonError::into_inner);
+    let saved = counter(table_path, key)?;
+    let id = format!("{}/{}", table_path, key);
+    if let Some((next, end)) = counters.get_mut(&id) {
+        if Some(*end) == saved && value < *end {
+            *next = (*next).max(value + 1); // in the reserved block
+            return Ok(());
+        }
+    }
+    counters.remove(&id);
+    let next = match saved {
+        Some(next) => next,
+        None => first()?,
+    };
+    if next <= value {
+        set(table_path, key, &(value + 1).to_string())?;
+    }
+    Ok(())
+}
+
+fn counter(table_path: &str, key: &str) -> Result<Option<usize>, JadbError> {
+    value(table_path, key)?
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| JadbError::Corrupt(format!("{} {}", key, value)))
+        })
+        .transpose()
+}
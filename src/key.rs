@@ -0,0 +1,218 @@
+// keys of a database, derived from a passphrase
+//
+// The rows of a database are encrypted with a random data key. The data key is saved in a keyfile, encrypted ("wrapped")
+// with a key derived from the passphrase using Argon2id. The keyfile looks like this:
+//
+// jadb key
+// kdf: argon2id
+// m cost: 19456
+// t cost: 2
+// p cost: 1
+// salt: <hex>
+// wrapped key: <hex of nonce and ciphertext>
+//
+// Because only the wrapped key depends on the passphrase, the passphrase can be changed without touching any rows.
+// While the data key is rotated, the keyfile also contains the previous data key in an `old wrapped key` line,
+// so an interrupted rotation can be finished the next time the database is opened.
+//
+// Every row records the id of the key it is encrypted with, see `RowCipher::key_id()`.
+
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::{Aead, NewAead, Payload};
+use aes_gcm::{Aes128Gcm, Key};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::RngCore;
+
+use crate::JadbError;
+
+/// Name of the keyfile in the database root, if no other keyfile is given.
+pub(crate) const KEYFILE_NAME: &str = "key.jadb";
+
+const KEY_LEN: usize = 16; // AES-128
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const WRAP_AAD: &[u8] = b"jadb key";
+/// The contents of a keyfile.
+pub(crate) struct Keyfile {
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: Vec<u8>,
+    wrapped_key: Vec<u8>,
+    old_wrapped_key: Option<Vec<u8>>, // previous data key, while it is rotated
+}
+
+impl Keyfile {
+    /// Creates a keyfile for a new random data key, wrapped with the passphrase.
+    pub(crate) fn generate(passphrase: &str) -> Result<(Keyfile, Aes128Gcm), JadbError> {
+        let mut data_key = [0u8; KEY_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut data_key);
+        let mut salt = vec![0u8; SALT_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut salt);
+        let mut keyfile = Keyfile {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+            salt,
+            wrapped_key: vec![],
+            old_wrapped_key: None,
+        };
+        keyfile.wrap(passphrase, &data_key)?;
+        Ok((keyfile, Aes128Gcm::new(Key::from_slice(&data_key))))
+    }
+    /// Reads the keyfile at the given path.
+    pub(crate) fn load(path: &str) -> Result<Keyfile, JadbError> {
+        let content = std::fs::read_to_string(path).map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => JadbError::NotFound(path.to_string()),
+            _ => JadbError::Io(err),
+        })?;
+        let corrupt = || JadbError::Corrupt(format!("{} isn't a valid keyfile", path));
+        let mut lines = content.lines();
+        if lines.next() != Some("jadb key") {
+            return Err(corrupt());
+        }
+        let values: Vec<(&str, &str)> = lines.filter_map(|line| line.split_once(": ")).collect();
+        let value = |key: &str| {
+            values
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, value)| *value)
+                .ok_or_else(corrupt)
+        };
+        if value("kdf")? != "argon2id" {
+            return Err(corrupt());
+        }
+        let number = |key: &str| value(key)?.parse::<u32>().map_err(|_| corrupt());
+        Ok(Keyfile {
+            m_cost: number("m cost")?,
+            t_cost: number("t cost")?,
+            p_cost: number("p cost")?,
+            salt: from_hex(value("salt")?).ok_or_else(corrupt)?,
+            wrapped_key: from_hex(value("wrapped key")?).ok_or_else(corrupt)?,
+            old_wrapped_key: match value("old wrapped key") {
+                Ok(hex) => Some(from_hex(hex).ok_or_else(corrupt)?),
+                Err(_) => None, // no rotation in progress
+            },
+        })
+    }
+    /// Writes the keyfile to the given path. It is written to a synced temporary file first, so the key can't be lost half way.
+    pub(crate) fn save(&self, path: &str) -> Result<(), JadbError> {
+        let mut content = format!(
+            "jadb key\nkdf: argon2id\nm cost: {}\nt cost: {}\np cost: {}\nsalt: {}\nwrapped key: {}",
+            self.m_cost,
+            self.t_cost,
+            self.p_cost,
+            to_hex(&self.salt),
+            to_hex(&self.wrapped_key)
+        );
+        if let Some(old_wrapped_key) = &self.old_wrapped_key {
+            content.push_str(&format!("\nold wrapped key: {}", to_hex(old_wrapped_key)));
+        }
+        crate::replace_file(path, content.as_bytes())
+    }
+    /// Unwraps the data key with the passphrase and returns a cipher using it.
+    pub(crate) fn unwrap(&self, passphrase: &str) -> Result<Aes128Gcm, JadbError> {
+        let data_key = self.unwrap_key(passphrase)?;
+        Ok(Aes128Gcm::new(Key::from_slice(&data_key)))
+    }
+    /// Unwraps the previous data key if a rotation is in progress.
+    pub(crate) fn unwrap_old(&self, passphrase: &str) -> Result<Option<Aes128Gcm>, JadbError> {
+        match &self.old_wrapped_key {
+            Some(old_wrapped_key) => {
+                let data_key = self.unwrap_wrapped(passphrase, old_wrapped_key)?;
+                Ok(Some(Aes128Gcm::new(Key::from_slice(&data_key))))
+            }
+            None => Ok(None),
+        }
+    }
+    /// Wraps the data key of this keyfile with a new passphrase and salt.
+    pub(crate) fn rewrap(
+        &mut self,
+        passphrase: &str,
+        new_passphrase: &str,
+    ) -> Result<(), JadbError> {
+        let data_key = self.unwrap_key(passphrase)?;
+        let old_data_key = match &self.old_wrapped_key {
+            Some(old_wrapped_key) => Some(self.unwrap_wrapped(passphrase, old_wrapped_key)?),
+            None => None,
+        };
+        rand::rngs::OsRng.fill_bytes(&mut self.salt);
+        if let Some(old_data_key) = old_data_key {
+            self.wrap(new_passphrase, &old_data_key)?;
+            self.old_wrapped_key = Some(std::mem::take(&mut self.wrapped_key));
+        }
+        self.wrap(new_passphrase, &data_key)
+    }
+    /// Replaces the data key with a new random one and keeps the current one as old key until the rotation is finished.
+    ///
+    /// Returns a cipher using the new data key.
+    pub(crate) fn begin_rotation(&mut self, passphrase: &str) -> Result<Aes128Gcm, JadbError> {
+        if self.old_wrapped_key.is_some() {
+            return Err(JadbError::RotationInProgress);
+        }
+        self.unwrap_key(passphrase)?; // check the passphrase
+        let mut data_key = [0u8; KEY_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut data_key);
+        self.old_wrapped_key = Some(std::mem::take(&mut self.wrapped_key));
+        self.wrap(passphrase, &data_key)?;
+        Ok(Aes128Gcm::new(Key::from_slice(&data_key)))
+    }
+    /// Forgets the old data key after all rows are re-encrypted with the new one.
+    pub(crate) fn finish_rotation(&mut self) {
+        self.old_wrapped_key = None;
+    }
+    fn unwrap_key(&self, passphrase: &str) -> Result<Vec<u8>, JadbError> {
+        self.unwrap_wrapped(passphrase, &self.wrapped_key)
+    }
+    fn unwrap_wrapped(&self, passphrase: &str, wrapped_key: &[u8]) -> Result<Vec<u8>, JadbError> {
+        if wrapped_key.len() < NONCE_LEN {
+            return Err(JadbError::Corrupt(String::from("wrapped key is too short")));
+        }
+        let (nonce, wrapped) = wrapped_key.split_at(NONCE_LEN);
+        let payload = Payload {
+            msg: wrapped,
+            aad: WRAP_AAD,
+        };
+        self.key_cipher(passphrase)?
+            .decrypt(GenericArray::from_slice(nonce), payload)
+            .map_err(|_| JadbError::WrongPassphrase)
+    }
+    fn wrap(&mut self, passphrase: &str, data_key: &[u8]) -> Result<(), JadbError> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut nonce);
+        let payload = Payload {
+            msg: data_key,
+            aad: WRAP_AAD,
+        };
+        let wrapped = self
+            .key_cipher(passphrase)?
+            .encrypt(GenericArray::from_slice(&nonce), payload)
+            .map_err(|_| JadbError::Encrypt)?;
+        self.wrapped_key = [&nonce[..], &wrapped].concat();
+        Ok(())
+    }
+    fn key_cipher(&self, passphrase: &str) -> Result<Aes128Gcm, JadbError> {
+        // key encryption key, derived from the passphrase
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(KEY_LEN))
+            .map_err(|err| JadbError::Corrupt(format!("invalid key parameters: {}", err)))?;
+        let mut key = [0u8; KEY_LEN];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &self.salt, &mut key)
+            .map_err(|err| JadbError::Corrupt(format!("couldn't derive key: {}", err)))?;
+        Ok(Aes128Gcm::new(Key::from_slice(&key)))
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -9,19 +9,67 @@
 //! | ----------- | ----------- |
 //! | \n | delimiter between fields |
 //! | \|o | replace with old content of row |
+//!
+//! [`Table::write()`] only knows these two, every other field is written as it is. [`Table::update_fields()`] knows these operators as well:
+//!
+//! | Operator | Function |
+//! | ----------- | ----------- |
+//! | \|a*text* | append the text to the old content |
+//! | \|+*number*, \|-*number* | increment or decrement the old number |
+//! | \|n | null |
+//! | \|d | default of the column, see [`Schema`] |
+//! | \|i*text* | insert a new field before the old one |
+//! | \|r | remove the old field |
+//! | \|\|*text* | the text starting with a single `\|` |
+//!
+//! Every operator except `|i` uses the old field at its position and moves on to the next one, see [`Update`].
+//! Fields containing `\n` can be written one by one with [`Table::write_fields()`], binary data as [`Value`]s with [`Table::write_values()`]. Rows save every field with its length,
+//! so all fields, including empty ones, are read back exactly as written.
+//!
+//! ## Errors
+//!
+//! Every operation that can fail returns a `Result` with a [`JadbError`] describing what went wrong.
 
 // time
 extern crate chrono;
 
 // hashing
 use std::hash::{Hash, Hasher};
+use std::ops::RangeBounds;
 
 // en- and decryption
 extern crate aes_gcm;
 
-use aes_gcm::aead::generic_array::GenericArray;
-use aes_gcm::aead::Aead;
-use aes_gcm::Aes128Gcm;
+// nonces
+extern crate rand;
+
+use rand::RngCore;
+
+mod catalog;
+mod cipher;
+mod database;
+mod error;
+mod format;
+mod index;
+mod index_file;
+mod info;
+mod key;
+mod normalize;
+mod schema;
+mod segment;
+mod text;
+mod update;
+mod value;
+
+pub use cipher::{Algorithm, Plaintext, RowCipher};
+pub use database::{Database, TableHandle};
+pub use error::JadbError;
+pub use index::HashStorage;
+pub use normalize::{IndexOptions, Normalization};
+pub use schema::{Column, ColumnType, Schema};
+pub use text::{FullText, TextHit};
+pub use update::Update;
+pub use value::Value;
 
 /// # Table
 ///
@@ -57,7 +105,7 @@ pub struct Table<'a> {
 /// };
 /// ```
 
-#[derive(Copy, Clone, Hash)]
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Row {
     // Row
     pub pos: usize, // position (line) in Table
@@ -76,307 +124,1774 @@ pub struct Row {
 /// };
 /// ```
 
-#[derive(Copy, Clone, Hash)]
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Field {
     pub pos: usize, // position in Row
 }
 
-impl Table<'_> {
+/// # Hit
+///
+/// A hit is a field found by a search. It holds the table id, the row and the field the search term was found in.
+///
+/// ## Examples
+/// ```
+/// let hit = jadb::Hit {
+///     table: 0,
+///     row: jadb::Row { pos: 0 },
+///     field: jadb::Field { pos: 0 },
+/// };
+/// ```
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Hit {
+    pub table: usize, // table id
+    pub row: Row,
+    pub field: Field,
+}
+
+/// # Rows
+///
+/// An iterator over the rows of a table in position order, see [`Table::rows()`]. Every row is read when it is reached.
+pub struct Rows<'a> {
+    table: Table<'a>,
+    cipher: &'a dyn RowCipher,
+    positions: std::vec::IntoIter<Row>,
+}
+
+impl Iterator for Rows<'_> {
+    type Item = Result<(Row, Vec<String>), JadbError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let row = self.positions.next()?;
+        Some(
+            self.table
+                .read(row, self.cipher)
+                .map(|fields| (row, fields)),
+        )
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.positions.size_hint()
+    }
+}
+
+impl<'a> Table<'a> {
     /// # create()
     ///
     /// This creates a new table containing a info file.
     ///
     /// A new directory is created, where rows can be saved in the future. This function takes a Table struct. The path can either be relative or full.
-    /// The directory contains a info file with the table name, creation time and path.
+    /// The directory contains a info file with the table name, creation time, path, id, format version and cipher.
+    /// The table is encrypted with AES-128-GCM, use [`Table::create_with()`] for other algorithms.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::EmptyPath` if no path is given and `JadbError::AlreadyExists` if there already is something at the path.
+    ///
+    /// ## Examples
+    /// ```
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_create",
+    ///   id: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// table.create()?;
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create(&self) -> Result<(), JadbError> {
+        self.create_with(Algorithm::Aes128Gcm)
+    }
+    /// # create_with()
     ///
-    /// ## Panic
+    /// This creates a new table whose rows are encrypted with the given algorithm. See [`Table::create()`].
     ///
-    /// A 0 is returned if everything ran ok, else it returns 1 with a short explanation of what went wrong.
+    /// The algorithm is saved in the info file, so the table must be used with a cipher of this algorithm afterwards.
+    /// Tables created with [`Algorithm::Plaintext`] aren't encrypted and can be used with any cipher.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::EmptyPath` if no path is given and `JadbError::AlreadyExists` if there already is something at the path.
     ///
     /// ## Examples
     /// ```
-    /// use jadb;
+    /// use aes_gcm::{Aes256Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
     ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
     /// let table = jadb::Table {
-    ///   path: "mytable",
+    ///   path: "mytable_create_with",
     ///   id: 0,
     /// };
     ///
-    /// let mut hash_storage: Vec<Vec<std::collections::HashMap<String, usize>>> = vec![vec![std::collections::HashMap::new()]];
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes256Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaPdSgVkYp3s6v9y$B&"));
     ///
-    /// table.create();
+    /// table.create_with(jadb::Algorithm::Aes256Gcm)?;
     ///
-    /// table.delete(&mut hash_storage); // delete table afterwards
+    /// table.write("hi", jadb::Row { pos: 0 }, &mut hash_storage, &cipher)?;
+    ///
+    /// assert_eq!(table.algorithm()?, jadb::Algorithm::Aes256Gcm);
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn create(&self) -> i8 {
+    pub fn create_with(&self, algorithm: Algorithm) -> Result<(), JadbError> {
         if self.path.is_empty() {
             // can't create table without name
-            println!("no table path given, cannot create table");
-            return 1;
+            return Err(JadbError::EmptyPath);
         }
         if std::path::Path::new(self.path).exists() {
-            println!("table already exists at given path");
-            return 1;
-        }
-        println!("creating in {}", self.path);
-        let mut name = self.path.clone();
-        if self.path.contains("/") || self.path.contains(r#"\"#) {
-            // get actual name of table without rest of path
-            let (_, substr) = self.path.rsplit_once('/').unwrap();
-            name = substr;
+            return Err(JadbError::AlreadyExists(self.path.to_string()));
         }
+        let name = std::path::Path::new(self.path)
+            .file_name() // get actual name of table without rest of path
+            .and_then(|name| name.to_str())
+            .unwrap_or(self.path);
         let info = format!(
-            "jadb database\ntablename: {}\ncreated on: {}\npath: {}",
+            "jadb database\ntablename: {}\ncreated on: {}\npath: {}\nid: {}\nformat version: {}\ncipher: {}",
             name,
             chrono::offset::Local::now(),
-            self.path
+            self.path,
+            self.id,
+            format::FORMAT_VERSION,
+            algorithm.name()
         ); // info file content
-        std::fs::create_dir_all(self.path).expect("Couldn't create db directory.");
-        std::fs::write(format!("{}/{}", self.path, "info.jadb"), info)
-            .expect("Couldn't create info file."); // write info file
-        0 // if ok return 0
+        std::fs::create_dir_all(self.path)?;
+        std::fs::write(format!("{}/{}", self.path, "info.jadb"), info)?; // write info file
+        segment::forget(self.path); // rows of an earlier table at this path
+        Ok(())
+    }
+    /// # algorithm()
+    ///
+    /// This returns the algorithm the rows of the table are encrypted with, as saved in its info file.
+    ///
+    /// Tables created by older versions of jadb don't save it and are encrypted with AES-128-GCM.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NotFound` if the table has no info file and `JadbError::UnsupportedFormat` if the algorithm is unknown.
+    pub fn algorithm(&self) -> Result<Algorithm, JadbError> {
+        match info::value(self.path, "cipher")? {
+            Some(name) => Algorithm::from_name(&name).ok_or(JadbError::UnsupportedFormat(name)),
+            None => Ok(Algorithm::Aes128Gcm),
+        }
     }
     /// # write()
     ///
-    /// This writes a new row to the table.
+    /// This writes a new row to the table.
+    ///
+    /// The row is appended to the table's segment files, see [`Table::compact()`]. The fields are seperated using the delimiter `\n`, every part is a field, even an empty one.
+    /// Use [`Table::write_fields()`] to write fields containing `\n`.
+    /// If a Row is rewritten and `|o` is used instead of new data for a field, the old content of the field will be used for the new one.
+    /// In new rows `|o` is written as text, like every other field. Use [`Table::update_fields()`] for the other operators of the [crate documentation](crate).
+    /// Old fields after the last one are removed, use [`Table::update()`] to keep them.
+    /// A variable for storing the hash contents of all fields in all tables must be provided.
+    /// The new contents are appended to the table's index file as well, so the next [`init()`] doesn't have to decrypt the row.
+    /// If the table has a [`Schema`], every field is parsed as the type of its column, see [`ColumnType::parse()`]. Empty fields and fields missing at the end
+    /// get the default of their column, except in text columns where an empty field is the empty text.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::EmptyContent` if there is nothing to write, `JadbError::IndexOutOfRange` if `|o` is used for a field the old row doesn't have,
+    /// `JadbError::SchemaViolation` if the fields don't match the schema of the table and `JadbError::CipherMismatch` if the cipher doesn't use the algorithm of the table.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_write",
+    ///   id: 0,
+    /// };
+    ///
+    /// let row = jadb::Row {
+    ///   pos: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.write("hi\nyou", row, &mut hash_storage, &cipher)?; // write 'hi' and 'you' in seperate fields
+    ///
+    /// table.write("|o\neveryone", row, &mut hash_storage, &cipher)?; // take the first field at index 0 and replace it with old content, overwrite the second field with 'everyone'
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write(
+        &self,
+        content: &str,
+        row: Row,
+        hash_var: &mut HashStorage,
+        cipher: &dyn RowCipher,
+    ) -> Result<(), JadbError> {
+        if content.is_empty() {
+            // No need to create new row if no content
+            return Err(JadbError::EmptyContent);
+        }
+        let exists = segment::exists(self.path, row.pos)?;
+        let updates = content
+            .split('\n')
+            .map(|field| match field {
+                "|o" if exists => Update::Keep, // the other operators are only used by update_fields()
+                _ => Update::Set(Value::from(field)),
+            })
+            .collect();
+        self.update_row(row, self.parse_texts(updates)?, false, hash_var, cipher)
+    }
+    /// # update_fields()
+    ///
+    /// This rewrites a row like [`Table::write()`], but fields can use all operators of the [crate documentation](crate), e.g. `|+1` increments the old field.
+    ///
+    /// Every field is parsed with [`Update::parse()`], so a field starting with `|` must be written with `||`, e.g. `||a` writes `|a`.
+    /// Old fields after the last one are removed, use [`Table::update()`] to keep them.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::IndexOutOfRange` if an operator is used for a field the old row doesn't have, `JadbError::InvalidUpdate` if an operator
+    /// can't be applied to the old field and any error of [`Table::write()`].
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_update_fields",
+    ///   id: 0,
+    /// };
+    ///
+    /// let row = jadb::Row {
+    ///   pos: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.write("Anna\n41\n|a", row, &mut hash_storage, &cipher)?; // '|a' is written as it is
+    ///
+    /// table.update_fields("|a Smith\n|+1\n||b", row, &mut hash_storage, &cipher)?;
+    ///
+    /// assert_eq!(table.read(row, &cipher)?, vec!["Anna Smith", "42", "|b"]);
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn update_fields(
+        &self,
+        content: &str,
+        row: Row,
+        hash_var: &mut HashStorage,
+        cipher: &dyn RowCipher,
+    ) -> Result<(), JadbError> {
+        if content.is_empty() {
+            return Err(JadbError::EmptyContent);
+        }
+        let updates = content
+            .split('\n')
+            .map(Update::parse)
+            .collect::<Result<Vec<Update>, JadbError>>()?;
+        self.update_row(row, self.parse_texts(updates)?, false, hash_var, cipher)
+    }
+    /// # write_fields()
+    ///
+    /// This writes a row like [`Table::write()`], but every field is given on its own, so fields may contain `\n`.
+    ///
+    /// The fields are written as they are, without operators, and read back exactly as written. If the table has a [`Schema`],
+    /// they are parsed like with [`Table::write()`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::EmptyContent` if there are no fields and any error of [`Table::write()`].
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_write_fields",
+    ///   id: 0,
+    /// };
+    ///
+    /// let row = jadb::Row {
+    ///   pos: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.write_fields(&["Anna", "Main Street 1\nBerlin", "|o"], row, &mut hash_storage, &cipher)?;
+    ///
+    /// assert_eq!(table.read(row, &cipher)?, vec!["Anna", "Main Street 1\nBerlin", "|o"]);
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_fields(
+        &self,
+        fields: &[&str],
+        row: Row,
+        hash_var: &mut HashStorage,
+        cipher: &dyn RowCipher,
+    ) -> Result<(), JadbError> {
+        if fields.is_empty() {
+            return Err(JadbError::EmptyContent);
+        }
+        let updates = fields
+            .iter()
+            .map(|field| Update::Set(Value::from(*field)))
+            .collect();
+        self.update_row(row, self.parse_texts(updates)?, false, hash_var, cipher)
+    }
+    fn parse_texts(&self, updates: Vec<Update>) -> Result<Vec<Update>, JadbError> {
+        // texts are values of the column's type
+        let schema = match Schema::read(self.path)? {
+            Some(schema) => schema,
+            None => return Ok(updates),
+        };
+        let mut pos = 0; // of the field in the new row
+        let mut parsed = Vec::with_capacity(updates.len());
+        for update in updates {
+            let update = match update {
+                Update::Set(Value::Text(text)) => match schema.parse(pos, &text)? {
+                    Some(value) => Update::Set(value),
+                    None => Update::Default,
+                },
+                Update::Insert(Value::Text(text)) => {
+                    Update::Insert(match schema.parse(pos, &text)? {
+                        Some(value) => value,
+                        None => schema.columns[pos].default.clone().unwrap_or(Value::Null),
+                    })
+                }
+                update => update,
+            };
+            if update != Update::Remove {
+                pos += 1;
+            }
+            parsed.push(update);
+        }
+        Ok(parsed)
+    }
+    /// # update()
+    ///
+    /// This changes the fields of an existing row.
+    ///
+    /// The updates are applied to the old fields in order: every update except [`Update::Insert`] uses the old field at its position
+    /// and moves on to the next one, e.g. `[Update::Keep, Update::Increment(Value::Int(1))]` increments the second field.
+    /// Old fields after the last update are kept. The whole row is rewritten at once, so either all updates are applied or none.
+    /// Like with [`Table::write_values()`], the new values must match the [`Schema`] of the table, if it has one.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NotFound` if the row doesn't exist, `JadbError::IndexOutOfRange` if an update uses a field the old row doesn't have,
+    /// `JadbError::InvalidUpdate` if an update can't be applied to the old field, e.g. because it increments a text that isn't a number,
+    /// `JadbError::EmptyContent` if all fields are removed and any error of [`Table::write_values()`].
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    /// use jadb::{Update, Value};
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_update",
+    ///   id: 0,
+    /// };
+    ///
+    /// let row = jadb::Row {
+    ///   pos: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.write_values(&[Value::from("Anna"), Value::Int(41), Value::from("admin")], row, &mut hash_storage, &cipher)?;
+    ///
+    /// table.update(&[Update::Append(Value::from(" Smith")), Update::Increment(Value::Int(1))], row, &mut hash_storage, &cipher)?;
+    ///
+    /// assert_eq!(table.read(row, &cipher)?, vec!["Anna Smith", "42", "admin"]); // the last field is kept
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn update(
+        &self,
+        updates: &[Update],
+        row: Row,
+        hash_var: &mut HashStorage,
+        cipher: &dyn RowCipher,
+    ) -> Result<(), JadbError> {
+        if !segment::exists(self.path, row.pos)? {
+            return Err(JadbError::NotFound(format!("{}/{}", self.path, row.pos)));
+        }
+        self.update_row(row, updates.to_vec(), true, hash_var, cipher)
+    }
+    /// # insert()
+    ///
+    /// This writes a new row at the next free position of the table and returns it.
+    ///
+    /// The next position is counted in the `next row` line of the info file, so positions of deleted rows aren't used again.
+    /// Positions are reserved there in blocks, so the info file isn't rewritten on every insert. Positions reserved but not used
+    /// before the process ends are skipped.
+    /// Positions which already have a row, e.g. because it was written with [`Table::write()`], are skipped. The position is taken
+    /// before the row is written, so it isn't used again even if writing fails. The content is written like with [`Table::write()`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NotFound` if the table doesn't exist, `JadbError::Corrupt` if the counter in the info file isn't a number
+    /// and any error of [`Table::write()`].
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_insert",
+    ///   id: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// let first = table.insert("hi", &mut hash_storage, &cipher)?;
+    /// let second = table.insert("you", &mut hash_storage, &cipher)?;
+    ///
+    /// assert_eq!((first.pos, second.pos), (0, 1));
+    ///
+    /// first.delete(table, &mut hash_storage)?;
+    ///
+    /// assert_eq!(table.insert("everyone", &mut hash_storage, &cipher)?.pos, 2); // deleted positions aren't used again
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert(
+        &self,
+        content: &str,
+        hash_var: &mut HashStorage,
+        cipher: &dyn RowCipher,
+    ) -> Result<Row, JadbError> {
+        if content.is_empty() {
+            return Err(JadbError::EmptyContent);
+        }
+        let mut pos = info::take(self.path, "next row", || self.first_row())?;
+        while segment::exists(self.path, pos)? {
+            pos = info::take(self.path, "next row", || self.first_row())?; // written with an explicit position
+        }
+        let row = Row { pos };
+        self.write(content, row, hash_var, cipher)?;
+        Ok(row)
+    }
+    /// # insert_if_absent()
+    ///
+    /// This writes a new row like [`Table::write()`], but only if the table has no row at this position yet.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::AlreadyExists` if the row exists and any error of [`Table::write()`].
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_insert_if_absent",
+    ///   id: 0,
+    /// };
+    ///
+    /// let row = jadb::Row {
+    ///   pos: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.insert_if_absent("hi", row, &mut hash_storage, &cipher)?;
+    ///
+    /// assert!(matches!(
+    ///     table.insert_if_absent("you", row, &mut hash_storage, &cipher),
+    ///     Err(jadb::JadbError::AlreadyExists(_))
+    /// ));
+    ///
+    /// assert_eq!(table.read(row, &cipher)?, vec!["hi"]);
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert_if_absent(
+        &self,
+        content: &str,
+        row: Row,
+        hash_var: &mut HashStorage,
+        cipher: &dyn RowCipher,
+    ) -> Result<(), JadbError> {
+        if segment::exists(self.path, row.pos)? {
+            return Err(JadbError::AlreadyExists(format!(
+                "{}/{}",
+                self.path, row.pos
+            )));
+        }
+        self.write(content, row, hash_var, cipher)?;
+        info::skip(self.path, "next row", row.pos, || self.first_row()) // so insert doesn't use it again after it is deleted
+    }
+    fn first_row(&self) -> Result<usize, JadbError> {
+        // tables without a counter in the info file, e.g. of older versions, start after their last row
+        Ok(self
+            .row_positions()?
+            .iter()
+            .map(|row| row.pos + 1)
+            .max()
+            .unwrap_or(0))
+    }
+    fn update_row(
+        &self,
+        row: Row,
+        updates: Vec<Update>,
+        keep_rest: bool,
+        hash_var: &mut HashStorage,
+        cipher: &dyn RowCipher,
+    ) -> Result<(), JadbError> {
+        let cipher = self.row_cipher(cipher)?;
+        let con_old_row: Vec<Value> = if segment::exists(self.path, row.pos)? {
+            // if row already exists, read old content
+            self.read_values(row, cipher)?
+        } else {
+            vec![]
+        };
+        let values = update::apply(&con_old_row, updates, keep_rest)?;
+        if values.is_empty() {
+            return Err(JadbError::EmptyContent); // every field was removed
+        }
+        let values = match Schema::read(self.path)? {
+            Some(schema) => schema.conform(values)?,
+            None => values
+                .into_iter()
+                .map(|value| value.unwrap_or(Value::Null))
+                .collect(),
+        };
+        self.write_row(row, values, hash_var, cipher)
+    }
+    /// # write_values()
+    ///
+    /// This writes a row of typed values to the table.
+    ///
+    /// The values are saved in a binary encoding, so numbers and bytes are read back exactly as written and texts may contain `\n`.
+    /// Like with [`Table::write()`], the hash storage and the index file are updated. Values are found by searches in their text form, see [`Value`].
+    /// If the table has a [`Schema`], values missing at the end get the default of their column and integers in float columns are converted.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::EmptyContent` if there are no values, `JadbError::SchemaViolation` if they don't match the schema of the table,
+    /// `JadbError::TooLong` if a text or bytes value is 4 GiB or longer and `JadbError::CipherMismatch` if the cipher doesn't use the algorithm of the table.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    /// use jadb::Value;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_write_values",
+    ///   id: 0,
+    /// };
+    ///
+    /// let row = jadb::Row {
+    ///   pos: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.write_values(&[Value::from("Anna"), Value::Int(42), Value::Float(0.1), Value::Null], row, &mut hash_storage, &cipher)?;
+    ///
+    /// assert_eq!(table.read_values(row, &cipher)?[2], Value::Float(0.1));
+    ///
+    /// assert_eq!(table.read(row, &cipher)?, vec!["Anna", "42", "0.1", ""]);
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_values(
+        &self,
+        values: &[Value],
+        row: Row,
+        hash_var: &mut HashStorage,
+        cipher: &dyn RowCipher,
+    ) -> Result<(), JadbError> {
+        if values.is_empty() {
+            return Err(JadbError::EmptyContent);
+        }
+        let cipher = self.row_cipher(cipher)?;
+        let values = match Schema::read(self.path)? {
+            Some(schema) => schema.conform(values.iter().cloned().map(Some).collect())?,
+            None => values.to_vec(),
+        };
+        self.write_row(row, values, hash_var, cipher)
+    }
+    fn write_row(
+        &self,
+        row: Row,
+        values: Vec<Value>,
+        hash_var: &mut HashStorage,
+        cipher: &dyn RowCipher,
+    ) -> Result<(), JadbError> {
+        let entry = index_file::Entry {
+            fingerprint: self.encrypt_row(row, &value::encode_row(&values)?, cipher)?,
+            fields: values,
+        };
+        index_file::append(*self, cipher, row.pos, &entry)?; // save new content in index file
+        hash_var.set_row(self.id, row, entry.fields); // replace old content in hash storage
+        Ok(())
+    }
+    /// # read()
+    ///
+    /// Using this function you can read tables.
+    ///
+    /// This function returns a Vector with Strings. Each String consists of a field from the row that was read.
+    /// Typed values are returned in their text form, use [`Table::read_values()`] to get them with their types.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NotFound` if the row doesn't exist, `JadbError::Misplaced` if the row was written for another row or table,
+    /// `JadbError::UnknownKey` if it is encrypted with another key, `JadbError::Decrypt` if it can't be decrypted with the given cipher or was tampered with
+    /// and `JadbError::Corrupt` if its record in the segment file was changed.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_read",
+    ///   id: 0,
+    /// };
+    ///
+    /// let row = jadb::Row {
+    ///   pos: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.write("hi\nyou", row, &mut hash_storage, &cipher)?; // write 'hi' and 'you' in seperate fields
+    ///
+    /// let row_contents: Vec<String> = table.read(row, &cipher)?;
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read(&self, row: Row, cipher: &dyn RowCipher) -> Result<Vec<String>, JadbError> {
+        self.read_with_keys(row, &[cipher])
+    }
+    /// # read_values()
+    ///
+    /// This reads the typed values of a row, see [`Table::write_values()`].
+    ///
+    /// Fields written with [`Table::write()`] and rows written by older versions of jadb are returned as [`Value::Text`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns any error of [`Table::read()`], and `JadbError::Corrupt` if the values can't be decoded.
+    pub fn read_values(&self, row: Row, cipher: &dyn RowCipher) -> Result<Vec<Value>, JadbError> {
+        Ok(self.decrypt_row(row, &[cipher])?.0)
+    }
+    /// # read_with_keys()
+    ///
+    /// This reads a row of a table whose rows may be encrypted with different keys, e.g. because a key rotation was interrupted.
+    ///
+    /// Every row records the id of the key it is encrypted with, so the matching cipher is picked from the given ones.
+    /// Rows written by older versions of jadb don't record a key id, all ciphers are tried for them.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::UnknownKey` if the row is encrypted with none of the given keys and any error of [`Table::read()`].
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_read_with_keys",
+    ///   id: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let old_cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    /// let new_cipher = Aes128Gcm::new(Key::from_slice(b"q3t6w9z$C&F)J@Nc"));
+    ///
+    /// table.create()?;
+    ///
+    /// table.write("hi", jadb::Row { pos: 0 }, &mut hash_storage, &old_cipher)?;
+    /// table.write("you", jadb::Row { pos: 1 }, &mut hash_storage, &new_cipher)?;
+    ///
+    /// assert_eq!(table.read_with_keys(jadb::Row { pos: 0 }, &[&new_cipher, &old_cipher])?, vec!["hi"]);
+    /// assert_eq!(table.read_with_keys(jadb::Row { pos: 1 }, &[&new_cipher, &old_cipher])?, vec!["you"]);
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_with_keys(
+        &self,
+        row: Row,
+        ciphers: &[&dyn RowCipher],
+    ) -> Result<Vec<String>, JadbError> {
+        let (values, _) = self.decrypt_row(row, ciphers)?;
+        Ok(values.iter().map(Value::to_string).collect())
+    }
+    /// # migrate()
+    ///
+    /// This rewrites all rows which were written by older versions of jadb in the current format.
+    ///
+    /// Rows written by older versions of jadb can still be read, but should be migrated: The oldest ones use a nonce derived from the table id and row position,
+    /// which is reused every time they are rewritten and depends on a hash function that may change with the Rust version.
+    /// Up to format version 5, every row was saved in its own file, these rows are moved to the table's segment files.
+    /// The table is compacted afterwards, see [`Table::compact()`]. The number of migrated rows is returned.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_migrate",
+    ///   id: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// table.write("hi\nyou", jadb::Row { pos: 0 }, &mut hash_storage, &cipher)?;
+    ///
+    /// assert_eq!(table.migrate(&cipher)?, 0); // new rows don't need to be migrated
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn migrate(&self, cipher: &dyn RowCipher) -> Result<usize, JadbError> {
+        let cipher = self.row_cipher(cipher)?;
+        self.rewrite_stale(&[cipher])
+    }
+    /// # rotate_key()
+    ///
+    /// This re-encrypts every row of the table, which is encrypted with the old key, with the new key.
+    ///
+    /// Every new row is appended to the table's segment files, so a row is never lost half way, and the rows encrypted with the old key
+    /// are removed from the disk by compacting the table afterwards, see [`Table::compact()`].
+    /// Rows already encrypted with the new key are skipped, so an interrupted rotation is resumed by calling this again with the same keys.
+    /// Until then, the table can be read with both keys using [`Table::read_with_keys()`]. Rows of older format versions are migrated on the way.
+    /// The number of re-encrypted rows is returned.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::UnknownKey` if a row is encrypted with neither of the keys and any error of [`Table::read()`].
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_rotate_key",
+    ///   id: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let old_cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    /// let new_cipher = Aes128Gcm::new(Key::from_slice(b"q3t6w9z$C&F)J@Nc"));
+    ///
+    /// table.create()?;
+    ///
+    /// table.write("hi\nyou", jadb::Row { pos: 0 }, &mut hash_storage, &old_cipher)?;
+    ///
+    /// assert_eq!(table.rotate_key(&old_cipher, &new_cipher)?, 1);
+    ///
+    /// assert_eq!(table.read(jadb::Row { pos: 0 }, &new_cipher)?, vec!["hi", "you"]);
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rotate_key(&self, old: &dyn RowCipher, new: &dyn RowCipher) -> Result<usize, JadbError> {
+        if self.algorithm()? == Algorithm::Plaintext {
+            return self.migrate(new); // there is no key to rotate
+        }
+        let rotated = self.rewrite_stale(&[new, old])?;
+        info::set(self.path, "cipher", new.algorithm().name())?; // the new key may use another algorithm
+        Ok(rotated)
+    }
+    /// # compact()
+    ///
+    /// This removes replaced and deleted rows from the table's segment files.
+    ///
+    /// Rows are appended to the segment files of the table, so their old contents stay on the disk when they are rewritten or deleted.
+    /// Compacting copies the current rows to new segment files and removes the old ones. It is done when a segment file is full
+    /// and more than half of the segments are old rows, and by [`Table::migrate()`] and [`Table::rotate_key()`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NotFound` if the table doesn't exist and `JadbError::Corrupt` if the record of a row in a segment file was changed.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_compact",
+    ///   id: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.write("hi", jadb::Row { pos: 0 }, &mut hash_storage, &cipher)?;
+    /// table.write("you", jadb::Row { pos: 0 }, &mut hash_storage, &cipher)?;
+    /// table.write("secret", jadb::Row { pos: 1 }, &mut hash_storage, &cipher)?;
+    ///
+    /// jadb::Row { pos: 1 }.delete(table, &mut hash_storage)?;
+    ///
+    /// table.compact()?; // 'hi' and 'secret' are removed from the disk
+    ///
+    /// assert_eq!(table.read(jadb::Row { pos: 0 }, &cipher)?, vec!["you"]);
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compact(&self) -> Result<(), JadbError> {
+        segment::compact(self.path)
+    }
+    fn rewrite_stale(&self, ciphers: &[&dyn RowCipher]) -> Result<usize, JadbError> {
+        // rewrite every row that isn't in the current format or encrypted with the first cipher,
+        // and the index file, which is encrypted like the rows
+        let mut rewritten = 0;
+        let mut entries = std::collections::BTreeMap::new();
+        for (pos, fingerprint) in segment::fingerprints(self.path)? {
+            let row = Row { pos };
+            let (values, stale) = self.decrypt_row(row, ciphers)?;
+            let fingerprint = if stale {
+                rewritten += 1;
+                self.encrypt_row(row, &value::encode_row(&values)?, ciphers[0])?
+            } else {
+                fingerprint
+            };
+            entries.insert(
+                row.pos,
+                index_file::Entry {
+                    fingerprint,
+                    fields: values,
+                },
+            );
+        }
+        index_file::save(*self, ciphers[0], &entries)?;
+        info::set(
+            self.path,
+            "format version",
+            &format::FORMAT_VERSION.to_string(),
+        )?; // all rows are in the current format now
+        segment::compact(self.path)?; // remove the replaced rows from the disk
+        Ok(rewritten)
+    }
+    fn row_cipher<'c>(&self, cipher: &'c dyn RowCipher) -> Result<&'c dyn RowCipher, JadbError> {
+        // the cipher new rows of this table are encrypted with
+        match self.algorithm()? {
+            Algorithm::Plaintext => Ok(&Plaintext),
+            algorithm if algorithm == cipher.algorithm() => Ok(cipher),
+            algorithm => Err(JadbError::CipherMismatch {
+                expected: algorithm,
+                found: cipher.algorithm(),
+            }),
+        }
+    }
+    fn encrypt_row(
+        &self,
+        row: Row,
+        plaintext: &[u8],
+        cipher: &dyn RowCipher,
+    ) -> Result<u64, JadbError> {
+        // row: header with key id, cipher and random nonce followed by the ciphertext, returns its fingerprint
+        let algorithm = cipher.algorithm();
+        let mut nonce = vec![0u8; algorithm.nonce_len()];
+        rand::rngs::OsRng.fill_bytes(&mut nonce);
+        let mut content = format::row_header(self.id, row.pos, cipher.key_id(), algorithm, &nonce);
+        let con_enc = cipher.encrypt(&nonce, plaintext, &content)?; // bind ciphertext to table and row
+        content.extend_from_slice(&con_enc);
+        segment::write(self.path, row.pos, &content) // appended, so the old row stays until the new one is complete
+    }
+    fn decrypt_row(
+        &self,
+        row: Row,
+        ciphers: &[&dyn RowCipher],
+    ) -> Result<(Vec<Value>, bool), JadbError> {
+        // returns the values and whether the row is stale: written in an older format or with another than the first cipher
+        let path = format!("{}/{}", self.path, row.pos);
+        let content =
+            segment::read(self.path, row.pos)?.ok_or_else(|| JadbError::NotFound(path.clone()))?;
+        if let Some(file) = format::split_row(&content)? {
+            if file.version >= 2 && (file.table_id != self.id as u64 || file.pos != row.pos as u64)
+            {
+                // row was copied from another row or table
+                return Err(JadbError::Misplaced {
+                    path,
+                    table: file.table_id,
+                    row: file.pos,
+                });
+            }
+            let outdated = file.version != format::FORMAT_VERSION;
+            if file.algorithm == Algorithm::Plaintext {
+                // plaintext rows can be read with any cipher, but only in plaintext tables: their checksum can be forged by anyone
+                if self.algorithm()? != Algorithm::Plaintext {
+                    return Err(JadbError::Decrypt(path));
+                }
+                let con = Plaintext
+                    .decrypt(file.nonce, file.ciphertext, file.aad)
+                    .ok_or(JadbError::Decrypt(path))?;
+                return Ok((to_values(Some(file.version), &con)?, outdated));
+            }
+            let same_algorithm: Vec<(usize, &&dyn RowCipher)> = ciphers
+                .iter()
+                .enumerate()
+                .filter(|(_, cipher)| cipher.algorithm() == file.algorithm)
+                .collect();
+            if let (true, Some(cipher)) = (same_algorithm.is_empty(), ciphers.first()) {
+                return Err(JadbError::CipherMismatch {
+                    expected: file.algorithm,
+                    found: cipher.algorithm(),
+                });
+            }
+            let candidates = match file.key_id {
+                Some(key_id) => {
+                    // only the cipher with the recorded key can decrypt the row
+                    let found = same_algorithm
+                        .into_iter()
+                        .find(|(_, cipher)| cipher.key_id() == key_id)
+                        .ok_or(JadbError::UnknownKey {
+                            path: path.clone(),
+                            key_id,
+                        })?;
+                    vec![found]
+                }
+                None => same_algorithm,
+            };
+            for (i, cipher) in candidates {
+                if let Some(con) = cipher.decrypt(file.nonce, file.ciphertext, file.aad) {
+                    return Ok((to_values(Some(file.version), &con)?, outdated || i != 0));
+                }
+            }
+            return Err(JadbError::Decrypt(path));
+        }
+        for cipher in ciphers {
+            if content.len() >= 12 {
+                // rows without header start with their nonce
+                let (nonce, con_enc) = content.split_at(12);
+                if let Some(con) = cipher.decrypt(nonce, con_enc, &[]) {
+                    return Ok((to_values(None, &con)?, true));
+                }
+            }
+            // the oldest rows have no nonce at all, it is derived from table id and row position
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            let id = format!("{}-{}", self.id, row.pos); // unique id
+            id.hash(&mut hasher);
+            let id_hash = hasher.finish().to_string();
+            let nonce = &id_hash.as_bytes()[..12]; // first 12 characters of id hash
+            if let Some(con) = cipher.decrypt(nonce, &content, &[]) {
+                return Ok((to_values(None, &con)?, true));
+            }
+        }
+        Err(JadbError::Decrypt(path))
+    }
+    /// # rows()
+    ///
+    /// This returns an iterator over all rows of the table and their fields, ordered by position.
+    ///
+    /// The positions are listed when it is created, every row is read when the iterator reaches it.
+    /// A row which can't be read, e.g. because it was deleted in between, gives an error, the iterator continues with the next one.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NotFound` if the table doesn't exist and `JadbError::Corrupt` if the table directory contains a file that isn't a row.
+    /// The rows give any error of [`Table::read()`].
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_rows",
+    ///   id: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.write("you", jadb::Row { pos: 10 }, &mut hash_storage, &cipher)?;
+    /// table.write("hi", jadb::Row { pos: 2 }, &mut hash_storage, &cipher)?;
+    ///
+    /// for row in table.rows(&cipher)? {
+    ///     let (row, fields) = row?;
+    ///     println!("{}: {:?}", row.pos, fields); // row 2 comes first
+    /// }
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rows<'c>(&self, cipher: &'c dyn RowCipher) -> Result<Rows<'c>, JadbError>
+    where
+        'a: 'c,
+    {
+        Ok(Rows {
+            table: *self,
+            cipher,
+            positions: self.row_positions()?.into_iter(),
+        })
+    }
+    /// # keys()
+    ///
+    /// This returns the positions of all rows of the table in order, without reading them.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NotFound` if the table doesn't exist and `JadbError::Corrupt` if the table directory contains a file that isn't a row.
+    pub fn keys(&self) -> Result<Vec<Row>, JadbError> {
+        self.row_positions()
+    }
+    /// # scan()
+    ///
+    /// This reads at most `limit` rows of the table, starting at the row at position `start` or the next one after it, ordered by position.
+    ///
+    /// To read the next page, start after the last row of this one. Only the returned rows are read.
+    ///
+    /// ## Errors
+    ///
+    /// Returns any error of [`Table::keys()`] and [`Table::read()`].
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_scan",
+    ///   id: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// for content in ["a", "b", "c", "d", "e"] {
+    ///     table.insert(content, &mut hash_storage, &cipher)?;
+    /// }
+    ///
+    /// let first_page = table.scan(jadb::Row { pos: 0 }, 2, &cipher)?;
+    /// let last = first_page.last().unwrap().0;
+    /// let second_page = table.scan(jadb::Row { pos: last.pos + 1 }, 2, &cipher)?;
+    ///
+    /// assert_eq!(second_page, vec![(jadb::Row { pos: 2 }, vec![String::from("c")]), (jadb::Row { pos: 3 }, vec![String::from("d")])]);
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn scan(
+        &self,
+        start: Row,
+        limit: usize,
+        cipher: &dyn RowCipher,
+    ) -> Result<Vec<(Row, Vec<String>)>, JadbError> {
+        self.scan_keys(start, limit)?
+            .into_iter()
+            .map(|row| Ok((row, self.read(row, cipher)?)))
+            .collect()
+    }
+    /// # scan_keys()
+    ///
+    /// This returns the positions of at most `limit` rows of the table, starting at `start` like [`Table::scan()`], without reading them.
+    ///
+    /// ## Errors
+    ///
+    /// Returns any error of [`Table::keys()`].
+    pub fn scan_keys(&self, start: Row, limit: usize) -> Result<Vec<Row>, JadbError> {
+        Ok(self
+            .row_positions()?
+            .into_iter()
+            .filter(|row| *row >= start)
+            .take(limit)
+            .collect())
+    }
+    fn row_positions(&self) -> Result<Vec<Row>, JadbError> {
+        // positions of all rows in the table's segments and row files of older versions, in order
+        Ok(segment::positions(self.path)?
+            .into_iter()
+            .map(|pos| Row { pos })
+            .collect())
+    }
+    /// # search()
+    ///
+    /// Using this you can search a table for a string.
+    ///
+    /// The term is looked up in the hash storage, so no row has to be read, no matter how large the table is.
+    ///
+    /// Every field of every row containing exactly the term is returned as a [`Hit`], ordered by row. If the term isn't found, a empty vector is returned.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_search",
+    ///   id: 0,
+    /// };
+    ///
+    /// let row = jadb::Row {
+    ///   pos: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.write("hi\nyou", row, &mut hash_storage, &cipher)?; // write 'hi' and 'you' in seperate fields
+    ///
+    /// table.write("you\nhi", jadb::Row { pos: 1 }, &mut hash_storage, &cipher)?;
+    ///
+    /// let hits: Vec<jadb::Hit> = table.search(String::from("hi"), &hash_storage);
+    ///
+    /// assert_eq!(hits.len(), 2); // found in both rows
+    /// assert_eq!(hits[1].field, jadb::Field { pos: 1 });
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search(&self, term: String, hash_var: &HashStorage) -> Vec<Hit> {
+        hash_var.get_in_table(self.id, &term)
+    }
+    /// # search_fuzzy()
+    ///
+    /// Using this you can search a table for fields which are at most `max_distance` edits away from a string, e.g. to find misspelled terms.
+    /// An edit inserts, removes or replaces a character, see [Levenshtein distance](https://en.wikipedia.org/wiki/Levenshtein_distance).
+    ///
+    /// The values of the hash storage are walked in order, and all values starting with a prefix that is already too far from the string are skipped at once.
+    ///
+    /// Every field of every row within the distance is returned as a [`Hit`], ordered by row and field.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_search_fuzzy",
+    ///   id: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.write("Smith\nSmyth\nSchmidt", jadb::Row { pos: 0 }, &mut hash_storage, &cipher)?;
+    ///
+    /// let hits: Vec<jadb::Hit> = table.search_fuzzy(String::from("Smth"), 1, &hash_storage);
+    ///
+    /// assert_eq!(hits.len(), 2); // 'Smith' and 'Smyth'
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_fuzzy(
+        &self,
+        term: String,
+        max_distance: usize,
+        hash_var: &HashStorage,
+    ) -> Vec<Hit> {
+        hash_var
+            .find(Some(self.id), |options| {
+                Ok(fuzzy_values(
+                    hash_var,
+                    &options.normalize(&term),
+                    max_distance,
+                ))
+            })
+            .unwrap_or_default()
+    }
+    /// # enable_full_text()
+    ///
+    /// This adds a full-text index to the table, so its fields can be searched for words with [`Table::search_text()`].
+    ///
+    /// The options are saved in the info file of the table, so [`init()`] indexes the words of every field from then on.
+    /// The index is built from the contents in the hash storage, so no row has to be read. Enabling it again replaces the options.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NotFound` if the table doesn't exist.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_enable_full_text",
+    ///   id: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.enable_full_text(jadb::FullText::default(), &mut hash_storage)?;
+    ///
+    /// assert_eq!(hash_storage.full_text(table.id), Some(jadb::FullText::default()));
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn enable_full_text(
+        &self,
+        options: FullText,
+        hash_var: &mut HashStorage,
+    ) -> Result<(), JadbError> {
+        info::read(self.path)?; // check that the table exists
+        info::set(self.path, "full text", &options.info())?;
+        hash_var.set_full_text(self.id, Some(options));
+        Ok(())
+    }
+    /// # set_index_options()
+    ///
+    /// This changes how the values of the table are indexed, e.g. to make searches ignore case.
+    ///
+    /// The values are normalized when they are written and the search terms when they are searched, so e.g. with case folding
+    /// and [`Normalization::Nfc`] a search for `café` finds `Café`, no matter if the `é` is written as one or as two characters.
+    /// The rows keep the values as written. The options are saved in the info file of the table and are used by [`init()`] from then on.
+    /// The values of the table are indexed again from the hash storage, so no row has to be read.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NotFound` if the table doesn't exist.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_set_index_options",
+    ///   id: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.write("Cafe\u{301}", jadb::Row { pos: 0 }, &mut hash_storage, &cipher)?; // 'e' followed by a combining accent
+    ///
+    /// assert!(table.search(String::from("caf\u{e9}"), &hash_storage).is_empty());
+    ///
+    /// let options = jadb::IndexOptions { case_folding: true, normalization: jadb::Normalization::Nfc };
+    ///
+    /// table.set_index_options(options, &mut hash_storage)?;
+    ///
+    /// assert_eq!(table.search(String::from("caf\u{e9}"), &hash_storage).len(), 1);
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_index_options(
+        &self,
+        options: IndexOptions,
+        hash_var: &mut HashStorage,
+    ) -> Result<(), JadbError> {
+        info::read(self.path)?; // check that the table exists
+        match options == IndexOptions::default() {
+            true => info::remove(self.path, "index")?,
+            false => info::set(self.path, "index", &options.info())?,
+        }
+        hash_var.set_index_options(self.id, options);
+        Ok(())
+    }
+    /// # set_schema()
+    ///
+    /// This gives the table named, typed columns, which every row written from then on must match.
+    ///
+    /// The schema is saved in the info file of the table. The rows of the table in the hash storage must already match it,
+    /// so initialize the table with [`init()`] before. An empty schema removes the schema of the table.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NotFound` if the table doesn't exist, `JadbError::InvalidName` if a column name isn't made of letters, digits and `_`,
+    /// `JadbError::AlreadyExists` if two columns have the same name and `JadbError::SchemaViolation` if a default or a row doesn't match its columns.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    /// use jadb::{Column, ColumnType, Schema, Value};
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_set_schema",
+    ///   id: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.set_schema(Schema { columns: vec![
+    ///     Column { name: String::from("name"), column_type: ColumnType::Text, nullable: false, default: None },
+    ///     Column { name: String::from("age"), column_type: ColumnType::Int, nullable: false, default: Some(Value::Int(0)) },
+    /// ] }, &hash_storage)?;
+    ///
+    /// table.write("Anna\n42", jadb::Row { pos: 0 }, &mut hash_storage, &cipher)?;
+    /// table.write("Ben", jadb::Row { pos: 1 }, &mut hash_storage, &cipher)?; // age is 0
+    ///
+    /// assert!(table.write("Carl\nold", jadb::Row { pos: 2 }, &mut hash_storage, &cipher).is_err()); // not an int
+    ///
+    /// let age = table.field("age")?;
+    ///
+    /// assert_eq!(table.read_values(jadb::Row { pos: 0 }, &cipher)?[age.pos], Value::Int(42));
+    /// assert_eq!(table.read_values(jadb::Row { pos: 1 }, &cipher)?[age.pos], Value::Int(0));
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_schema(&self, schema: Schema, hash_var: &HashStorage) -> Result<(), JadbError> {
+        info::read(self.path)?; // check that the table exists
+        schema.check()?;
+        if !schema.columns.is_empty() {
+            for (_, fields) in hash_var.table_rows(self.id) {
+                let conformed = schema.conform(fields.iter().cloned().map(Some).collect())?;
+                if conformed[..] != *fields {
+                    // rows keep their values until they are rewritten
+                    return Err(JadbError::SchemaViolation(String::from(
+                        "rows of the table would change",
+                    )));
+                }
+            }
+        }
+        match schema.columns.is_empty() {
+            true => Schema::save(self.path, None),
+            false => Schema::save(self.path, Some(&schema)),
+        }
+    }
+    /// # schema()
+    ///
+    /// This returns the schema of the table, if it has one. See [`Table::set_schema()`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NotFound` if the table doesn't exist and `JadbError::UnsupportedFormat` if the schema was saved by a newer version of jadb.
+    pub fn schema(&self) -> Result<Option<Schema>, JadbError> {
+        Schema::read(self.path)
+    }
+    /// # field()
+    ///
+    /// This returns the field of the column with the given name, so it can be used instead of its position. See [`Table::set_schema()`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::UnknownColumn` if the table has no schema or no column with this name and any error of [`Table::schema()`].
+    pub fn field(&self, name: &str) -> Result<Field, JadbError> {
+        self.schema()?
+            .and_then(|schema| schema.field(name))
+            .ok_or_else(|| JadbError::UnknownColumn(name.to_string()))
+    }
+    /// # disable_full_text()
+    ///
+    /// This removes the full-text index of the table.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NotFound` if the table doesn't exist.
+    pub fn disable_full_text(&self, hash_var: &mut HashStorage) -> Result<(), JadbError> {
+        info::read(self.path)?; // check that the table exists
+        info::remove(self.path, "full text")?;
+        hash_var.set_full_text(self.id, None);
+        Ok(())
+    }
+    /// # search_text()
+    ///
+    /// Using this you can search the words of a table's fields, see [`Table::enable_full_text()`].
+    ///
+    /// The query is split into words like the fields. Every field containing any of them is returned as a [`TextHit`], ranked by its
+    /// [BM25](https://en.wikipedia.org/wiki/Okapi_BM25) score with the best match first. Fields with rare words of the query and few other words
+    /// rank higher. The highlights are the byte ranges of the matching words in the field.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NoFullText` if the table has no full-text index.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_search_text",
+    ///   id: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.enable_full_text(jadb::FullText { stemming: true, stopwords: true }, &mut hash_storage)?;
+    ///
+    /// table.write("The quick brown cat\njumps over the lazy dog", jadb::Row { pos: 0 }, &mut hash_storage, &cipher)?;
+    ///
+    /// table.write("Cats and dogs", jadb::Row { pos: 1 }, &mut hash_storage, &cipher)?;
+    ///
+    /// let hits: Vec<jadb::TextHit> = table.search_text(String::from("cat"), &hash_storage)?;
+    ///
+    /// assert_eq!(hits.len(), 2);
+    /// assert_eq!(hits[0].hit.row, jadb::Row { pos: 1 }); // the shorter field ranks higher
+    /// assert_eq!(hits[0].highlights, vec![0..4]); // 'Cats'
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_text(
+        &self,
+        query: String,
+        hash_var: &HashStorage,
+    ) -> Result<Vec<TextHit>, JadbError> {
+        if hash_var.full_text(self.id).is_none() {
+            return Err(JadbError::NoFullText(self.path.to_string()));
+        }
+        Ok(hash_var.search_text(&query, Some(self.id)))
+    }
+    /// # search_prefix()
+    ///
+    /// Using this you can search a table for fields starting with a string.
+    ///
+    /// The values of the hash storage are kept in order, so only the values starting with the prefix are looked at.
+    ///
+    /// Every field of every row starting with the prefix is returned as a [`Hit`], ordered by row and field.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_search_prefix",
+    ///   id: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
     ///
-    /// A new file with the contents of the row is created. The fields are seperated using the delimiter `\n`.
-    /// If a Row is rewritten and `|o` is used instead of new data for a field, the old content of the field will be used for the new one.
-    /// A variable for storing the hash contents of all fields in all tables must be provided.
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.write("Anna\nSmith", jadb::Row { pos: 0 }, &mut hash_storage, &cipher)?;
+    ///
+    /// table.write("Annabel\nJones", jadb::Row { pos: 1 }, &mut hash_storage, &cipher)?;
+    ///
+    /// let hits: Vec<jadb::Hit> = table.search_prefix(String::from("Ann"), &hash_storage);
+    ///
+    /// assert_eq!(hits.len(), 2); // 'Anna' and 'Annabel'
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_prefix(&self, prefix: String, hash_var: &HashStorage) -> Vec<Hit> {
+        hash_var
+            .find(Some(self.id), |options| {
+                Ok(hash_var.values_with_prefix(&options.normalize(&prefix)))
+            })
+            .unwrap_or_default()
+    }
+    /// # search_contains()
     ///
-    /// ## Panic
+    /// Using this you can search a table for fields containing a string.
     ///
-    /// A 0 is returned if everything ran ok, else it returns 1 with a short explanation of what went wrong.
+    /// Only values containing every trigram of the string are compared with it, so most fields are never looked at.
+    /// Strings shorter than three characters have no trigrams and are compared with every value.
+    ///
+    /// Every field of every row containing the string is returned as a [`Hit`], ordered by row and field.
     ///
     /// ## Examples
     /// ```
-    /// use jadb;
     /// use aes_gcm::{Aes128Gcm, Key};
     /// use aes_gcm::aead::NewAead;
     ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
     /// let table = jadb::Table {
-    ///   path: "mytable",
+    ///   path: "mytable_search_contains",
     ///   id: 0,
     /// };
     ///
-    /// let row = jadb::Row {
-    ///   pos: 0,
-    /// };
-    ///
-    /// let mut hash_storage: Vec<Vec<std::collections::HashMap<String, usize>>> = vec![vec![std::collections::HashMap::new()]];
+    /// let mut hash_storage = jadb::HashStorage::new();
     ///
     /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
     ///
-    /// table.create();
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
     ///
-    /// jadb::init(table, &mut hash_storage, &cipher); // Initialize the hash storage
+    /// table.write("Anna\nSmith", jadb::Row { pos: 0 }, &mut hash_storage, &cipher)?;
     ///
-    /// table.write("hi\nyou", row, &mut hash_storage, &cipher); // write 'hi' and 'you' in seperate fields
+    /// table.write("Annabel\nJones", jadb::Row { pos: 1 }, &mut hash_storage, &cipher)?;
     ///
-    /// table.write("|o\neveryone", row, &mut hash_storage, &cipher); // take the first field at index 0 and replace it with old content, overwrite the second field with 'everyone'
+    /// let hits: Vec<jadb::Hit> = table.search_contains(String::from("nab"), &hash_storage);
     ///
-    /// table.delete(&mut hash_storage); // delete table afterwards
+    /// assert_eq!(hits, vec![jadb::Hit { table: 0, row: jadb::Row { pos: 1 }, field: jadb::Field { pos: 0 } }]);
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn write(
-        &self,
-        content: &str,
-        row: Row,
-        hash_var: &mut Vec<Vec<std::collections::HashMap<String, usize>>>,
-        cipher: &Aes128Gcm,
-    ) -> i8 {
-        if content.is_empty() {
-            // No need to create new row if no content
-            println!("Not writing because no content given.");
-            return 1;
-        }
-        println!(
-            "Writing {} to table path {} in Row {}",
-            content, self.path, row.pos
-        );
-        let path = format!("{}/{}", self.path, row.pos); // path for row file
-        let mut con_w_form: String = String::new();
-        let mut hasher = std::collections::hash_map::DefaultHasher::new(); // for hashing the nonce string
-        let id = format!("{}-{}", self.id, row.pos); // unique id
-        id.hash(&mut hasher);
-        let id_hash = hasher.finish().to_string();
-        let nonce = GenericArray::<u8, aes_gcm::aead::generic_array::typenum::U12>::from_slice(
-            &id_hash[..12].as_bytes(),
-        ); // use first 12 characters of id hash for nonce
-        if std::path::Path::new(&path).exists() {
-            // if row already exists
-            let mut con_str: Vec<&str> = content.split("\n").collect(); // split fields
-            let con_old_row: Vec<String> = self.read(row, cipher); // read old content
-            for i in 0..con_str.len() {
-                if con_str[i] == "|o" {
-                    // if told to get old content...
-                    con_str[i] = &con_old_row[i]; // overwrite '|o' with old content
-                }
-
-                con_w_form.push_str(con_str[i]);
-
-                if i != con_str.len() - 1 {
-                    con_w_form.push_str("\n"); // add delimiter: newline
-                }
-
-                if hash_var[self.id].len() <= row.pos {
-                    // if row hash var is too small
-                    hash_var[self.id].resize(row.pos + 1, std::collections::HashMap::new());
-                    // resize
-                }
-
-                hash_var[self.id][row.pos].insert(con_str[i].parse().unwrap(), i);
-                // add new content to hash variable
-            }
-
-            let con_enc = cipher
-                .encrypt(nonce, con_w_form.as_ref())
-                .expect("encryption failed");
-
-            std::fs::write(&path, &con_enc).expect("Couldn't write Row");
-        } else {
-            let con_str: Vec<&str> = content.split("\n").collect(); // split fields
-
-            for i in 0..con_str.len() {
-                // add new contents to hash storage
-                if hash_var[self.id].len() <= row.pos {
-                    // if row hash var is too small
-                    hash_var[self.id].resize(row.pos + 1, std::collections::HashMap::new());
-                    // resize
-                }
-
-                hash_var[self.id][row.pos].insert(con_str[i].parse().unwrap(), i);
-                // add new content to hash variable
-            }
-
-            let con_enc = cipher
-                .encrypt(nonce, content.as_ref())
-                .expect("encryption failed");
-
-            std::fs::write(&path, &con_enc).expect("Couldn't write Row");
-        }
-        0 // if ok return 0
+    pub fn search_contains(&self, text: String, hash_var: &HashStorage) -> Vec<Hit> {
+        hash_var
+            .find(Some(self.id), |options| {
+                Ok(hash_var.values_containing(&options.normalize(&text)))
+            })
+            .unwrap_or_default()
     }
-    /// # read()
+    /// # search_regex()
     ///
-    /// Using this function you can read tables.
+    /// Using this you can search a table for fields matching a regular expression, see the [`regex`](https://docs.rs/regex) crate for its syntax.
+    /// A field matches if the expression matches any part of it, use `^` and `$` to match whole fields.
     ///
-    /// This function returns a Vector with Strings. Each String consists of a field from the row that was read.
+    /// Texts every match has to contain are taken from the expression, and only values containing their trigrams are compared with it.
+    ///
+    /// Every field of every row matching the expression is returned as a [`Hit`], ordered by row and field.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::InvalidRegex` if the pattern isn't a valid regular expression.
     ///
     /// ## Examples
     /// ```
-    /// use jadb;
     /// use aes_gcm::{Aes128Gcm, Key};
     /// use aes_gcm::aead::NewAead;
     ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
     /// let table = jadb::Table {
-    ///   path: "mytable",
+    ///   path: "mytable_search_regex",
     ///   id: 0,
     /// };
     ///
-    /// let row = jadb::Row {
-    ///   pos: 0,
-    /// };
-    ///
-    /// let mut hash_storage: Vec<Vec<std::collections::HashMap<String, usize>>> = vec![vec![std::collections::HashMap::new()]];
+    /// let mut hash_storage = jadb::HashStorage::new();
     ///
     /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
     ///
-    /// table.create();
+    /// table.create()?;
     ///
-    /// jadb::init(table, &mut hash_storage, &cipher); // Initialize the hash storage
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
     ///
-    /// table.write("hi\nyou", row, &mut hash_storage, &cipher); // write 'hi' and 'you' in seperate fields
+    /// table.write("Anna\nSmith", jadb::Row { pos: 0 }, &mut hash_storage, &cipher)?;
     ///
-    /// let row_contents: Vec<String> = table.read(row, &cipher);
+    /// table.write("Annabel\nJones", jadb::Row { pos: 1 }, &mut hash_storage, &cipher)?;
     ///
-    /// table.delete(&mut hash_storage); // delete table afterwards
-    /// ```
-    pub fn read(&self, row: Row, cipher: &Aes128Gcm) -> Vec<String> {
-        let content =
-            std::fs::read(format!("{}/{}", self.path, row.pos)).expect("Couldn't read row");
-
-        let mut hasher = std::collections::hash_map::DefaultHasher::new();
-
-        let id = format!("{}-{}", self.id, row.pos); // unique id
-        id.hash(&mut hasher);
-        let id_hash = hasher.finish(); // create hash
-        let id_hash_str = id_hash.to_string();
-        let nonce = GenericArray::<u8, aes_gcm::aead::generic_array::typenum::U12>::from_slice(
-            &id_hash_str[..12].as_bytes(),
-        ); // use first 12 characters of id hash for nonce
-        let con_enc = cipher.decrypt(nonce, content.as_ref()).expect(&*format!(
-            "couldn't decrypt {:?} with nonce {:?} in row {} and table at {}",
-            content, nonce, row.pos, self.path
-        ));
-
-        println!("read not split enc {:?}", con_enc);
-        let con_split = split_by_delim(&con_enc, &10u8);
-        println!("read and split enc {:?}", con_split);
-
-        let mut final_array: Vec<String> = vec![];
-        for field in con_split {
-            final_array.push(
-                std::str::from_utf8(field)
-                    .expect(&*format!("could not convert {:?} to string", field))
-                    .parse()
-                    .unwrap(),
-            );
-        }
-        final_array
-    }
-    /// # search()
+    /// let hits: Vec<jadb::Hit> = table.search_regex(String::from("^(Smith|Jones)$"), &hash_storage)?;
     ///
-    /// Using this you can search a table for a string.
+    /// assert_eq!(hits.len(), 2); // the second field of both rows
     ///
-    /// The hash storage of this table is searched for the hash of the String that aims to be found.
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_regex(
+        &self,
+        pattern: String,
+        hash_var: &HashStorage,
+    ) -> Result<Vec<Hit>, JadbError> {
+        hash_var.find(Some(self.id), |options| {
+            hash_var.values_matching(&regex_for(&pattern, options))
+        })
+    }
+    /// # range()
     ///
-    /// ## Panic
+    /// Using this you can find the rows whose field at the position is within a range of numbers or timestamps.
     ///
-    /// If the term isn't found, a empty vector will be returned. Else a vector consisting of the table id, the row position and the field position will be returned.
+    /// Numbers and timestamps are kept in an ordered index of every field in the hash storage, so no row has to be read.
+    /// Integers and floats are compared by their value, see [`Value`]. Texts are never in a range, so e.g. fields written with [`Table::write()`]
+    /// are only found in tables with a [`Schema`], which gives them their column's type. If only one end of the range is given,
+    /// it is open for values of the same type, so `10..` finds all numbers from 10 but no timestamps.
+    /// A hit is returned for every matching row, ordered by value and row.
     ///
     /// ## Examples
     /// ```
-    /// use jadb;
     /// use aes_gcm::{Aes128Gcm, Key};
     /// use aes_gcm::aead::NewAead;
+    /// use jadb::Value;
     ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
     /// let table = jadb::Table {
-    ///   path: "mytable",
+    ///   path: "mytable_range",
     ///   id: 0,
     /// };
     ///
-    /// let row = jadb::Row {
-    ///   pos: 0,
-    /// };
-    ///
-    /// let mut hash_storage: Vec<Vec<std::collections::HashMap<String, usize>>> = vec![vec![std::collections::HashMap::new()]];
+    /// let mut hash_storage = jadb::HashStorage::new();
     ///
     /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
     ///
-    /// table.create();
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
     ///
-    /// jadb::init(table, &mut hash_storage, &cipher); // Initialize the hash storage
+    /// table.write_values(&[Value::from("Anna"), Value::Int(42)], jadb::Row { pos: 0 }, &mut hash_storage, &cipher)?;
+    /// table.write_values(&[Value::from("Ben"), Value::Int(17)], jadb::Row { pos: 1 }, &mut hash_storage, &cipher)?;
+    /// table.write_values(&[Value::from("Carl"), Value::Float(30.5)], jadb::Row { pos: 2 }, &mut hash_storage, &cipher)?;
     ///
-    /// table.write("hi\nyou", row, &mut hash_storage, &cipher); // write 'hi' and 'you' in seperate fields
+    /// let age = jadb::Field { pos: 1 };
     ///
-    /// let location: Vec<usize> = table.search(String::from("hi"), &mut hash_storage);
+    /// let hits = table.range(age, 18..50, &hash_storage);
     ///
-    /// table.delete(&mut hash_storage); // delete table afterwards
+    /// assert_eq!(hits.iter().map(|hit| hit.row.pos).collect::<Vec<usize>>(), vec![2, 0]); // ordered by age
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn search(
+    pub fn range<V: Into<Value> + Clone>(
         &self,
-        term: String,
-        hash_var: &Vec<Vec<std::collections::HashMap<String, usize>>>,
-    ) -> Vec<usize> {
-        for i in 0..hash_var[self.id].len() {
-            // search every row in table
-            return match hash_var[self.id][i].get(&term) {
-                // for term
-                Some(result) => vec![self.id, i, *result], // return [Table, Row, pos]
-                None => vec![],
-            };
-        }
-        vec![]
+        field: Field,
+        range: impl RangeBounds<V>,
+        hash_var: &HashStorage,
+    ) -> Vec<Hit> {
+        let start = range.start_bound().cloned().map(Into::into);
+        let end = range.end_bound().cloned().map(Into::into);
+        hash_var.range(self.id, field, (start, end))
     }
     /// # delete()
     ///
@@ -384,18 +1899,18 @@ impl Table<'_> {
     ///
     /// This deletes the directory where the table is located in and clears the table's values in the hash storage.
     ///
-    /// ## Panic
+    /// ## Errors
     ///
-    /// If it can't find the table, it will return 1, if it can delete it, it will return 0.
+    /// Returns `JadbError::NotFound` if there is no table at the path.
     ///
     /// ## Examples
     /// ```
-    /// use jadb;
     /// use aes_gcm::{Aes128Gcm, Key};
     /// use aes_gcm::aead::NewAead;
     ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
     /// let table = jadb::Table {
-    ///   path: "mytable",
+    ///   path: "mytable_delete",
     ///   id: 0,
     /// };
     ///
@@ -403,31 +1918,28 @@ impl Table<'_> {
     ///   pos: 0,
     /// };
     ///
-    /// let mut hash_storage: Vec<Vec<std::collections::HashMap<String, usize>>> = vec![vec![std::collections::HashMap::new()]];
+    /// let mut hash_storage = jadb::HashStorage::new();
     ///
     /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
     ///
-    /// table.create();
+    /// table.create()?;
     ///
-    /// jadb::init(table, &mut hash_storage, &cipher); // Initialize the hash storage
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
     ///
-    /// table.delete(&mut hash_storage);
+    /// table.delete(&mut hash_storage)?;
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn delete(&self, hash_var: &mut Vec<Vec<std::collections::HashMap<String, usize>>>) -> i8 {
+    pub fn delete(&self, hash_var: &mut HashStorage) -> Result<(), JadbError> {
         let info_path = format!("{}/{}", self.path, "info.jadb"); // create path of info file
-        return if std::path::Path::new(&info_path).exists() {
+        if !std::path::Path::new(&info_path).exists() {
             // use it to check if table exists
-            std::fs::remove_dir_all(self.path).expect("Couldn't delete database files."); // delete folder
-            hash_var[self.id].clear(); // and the HashMap
-            if self.id == hash_var.len() - 1 {
-                // if id of removed table is last element
-                hash_var.pop(); // remove last element
-            }
-            0
-        } else {
-            println!("Table doesn't exist at {}", self.path);
-            1
-        };
+            return Err(JadbError::NotFound(self.path.to_string()));
+        }
+        segment::forget(self.path); // while the path can still be resolved
+        std::fs::remove_dir_all(self.path)?; // delete folder
+        hash_var.remove_table(self.id); // and its contents in the hash storage
+        Ok(())
     }
 }
 /// # LenType
@@ -454,12 +1966,12 @@ impl Row {
     ///
     /// ## Examples
     /// ```
-    /// use jadb;
     /// use aes_gcm::{Aes128Gcm, Key};
     /// use aes_gcm::aead::NewAead;
     ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
     /// let table = jadb::Table {
-    ///   path: "mytable",
+    ///   path: "mytable_row_length",
     ///   id: 0,
     /// };
     ///
@@ -467,31 +1979,35 @@ impl Row {
     ///   pos: 0,
     /// };
     ///
-    /// let mut hash_storage: Vec<Vec<std::collections::HashMap<String, usize>>> = vec![vec![std::collections::HashMap::new()]];
+    /// let mut hash_storage = jadb::HashStorage::new();
     ///
     /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
     ///
-    /// table.create();
+    /// table.create()?;
     ///
-    /// jadb::init(table, &mut hash_storage, &cipher); // Initialize the hash storage
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
     ///
-    /// table.write("hi\nyou", row, &mut hash_storage, &cipher); // write 'hi' and 'you' in seperate fields
+    /// table.write("hi\nyou", row, &mut hash_storage, &cipher)?; // write 'hi' and 'you' in seperate fields
     ///
-    /// let length: i32 = row.length(table, jadb::LenType::Fields, &cipher);
+    /// let length: i32 = row.length(table, jadb::LenType::Fields, &cipher)?;
     ///
-    /// table.delete(&mut hash_storage); // delete table afterwards
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn length(&self, table: Table, utype: LenType, cipher: &Aes128Gcm) -> i32 {
-        let con = table.read(*self, cipher);
-        let mut len: i32 = 0;
-        if utype == LenType::Characters {
-            for i in 0..con.len() {
-                len += con[i].len() as i32;
-            }
+    pub fn length(
+        &self,
+        table: Table,
+        utype: LenType,
+        cipher: &dyn RowCipher,
+    ) -> Result<i32, JadbError> {
+        let con = table.read(*self, cipher)?;
+        let len = if utype == LenType::Characters {
+            con.iter().map(|field| field.len() as i32).sum()
         } else {
-            len = con.len() as i32;
-        }
-        len
+            con.len() as i32
+        };
+        Ok(len)
     }
     /// # shash()
     ///
@@ -499,12 +2015,12 @@ impl Row {
     ///
     /// ## Examples
     /// ```
-    /// use jadb;
     /// use aes_gcm::{Aes128Gcm, Key};
     /// use aes_gcm::aead::NewAead;
     ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
     /// let table = jadb::Table {
-    ///   path: "mytable",
+    ///   path: "mytable_row_shash",
     ///   id: 0,
     /// };
     ///
@@ -512,25 +2028,25 @@ impl Row {
     ///   pos: 0,
     /// };
     ///
-    /// let mut hash_storage: Vec<Vec<std::collections::HashMap<String, usize>>> = vec![vec![std::collections::HashMap::new()]];
+    /// let mut hash_storage = jadb::HashStorage::new();
     ///
     /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
     ///
-    /// table.create();
+    /// table.create()?;
     ///
-    /// jadb::init(table, &mut hash_storage, &cipher); // Initialize the hash storage
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
     ///
-    /// table.write("hi\nyou", row, &mut hash_storage, &cipher); // write 'hi' and 'you' in seperate fields
+    /// table.write("hi\nyou", row, &mut hash_storage, &cipher)?; // write 'hi' and 'you' in seperate fields
     ///
-    /// let hash: u64 = row.shash(table, &cipher);
+    /// let hash: u64 = row.shash(table, &cipher)?;
     ///
-    /// table.delete(&mut hash_storage); // delete table afterwards
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn shash(&self, table: Table, cipher: &Aes128Gcm) -> u64 {
-        let mut hasher = std::collections::hash_map::DefaultHasher::new();
-        let a: Vec<String> = table.read(*self, cipher);
-        a.hash(&mut hasher);
-        hasher.finish()
+    pub fn shash(&self, table: Table, cipher: &dyn RowCipher) -> Result<u64, JadbError> {
+        let a: Vec<String> = table.read(*self, cipher)?;
+        Ok(format::hash_fields(&a))
     }
     /// # shash_debug()
     ///
@@ -538,12 +2054,12 @@ impl Row {
     ///
     /// ## Examples
     /// ```
-    /// use jadb;
     /// use aes_gcm::{Aes128Gcm, Key};
     /// use aes_gcm::aead::NewAead;
     ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
     /// let table = jadb::Table {
-    ///   path: "mytable",
+    ///   path: "mytable_row_shash_debug",
     ///   id: 0,
     /// };
     ///
@@ -551,59 +2067,59 @@ impl Row {
     ///   pos: 0,
     /// };
     ///
-    /// let mut hash_storage: Vec<Vec<std::collections::HashMap<String, usize>>> = vec![vec![std::collections::HashMap::new()]];
+    /// let mut hash_storage = jadb::HashStorage::new();
     ///
     /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
     ///
-    /// table.create();
+    /// table.create()?;
     ///
-    /// jadb::init(table, &mut hash_storage, &cipher); // Initialize the hash storage
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
     ///
-    /// table.write("hey", row, &mut hash_storage, &cipher); // write 'hi' and 'you' in seperate fields
+    /// table.write("hey", row, &mut hash_storage, &cipher)?; // write 'hey' in a single field
     ///
-    /// let hash: u64 = row.shash_debug(table, "hey", &cipher);
+    /// let hash: u64 = row.shash_debug(table, "hey", &cipher)?;
     ///
-    /// table.delete(&mut hash_storage); // delete table afterwards
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn shash_debug(&self, table: Table, test_con: &str, cipher: &Aes128Gcm) -> u64 {
+    pub fn shash_debug(
+        &self,
+        table: Table,
+        test_con: &str,
+        cipher: &dyn RowCipher,
+    ) -> Result<u64, JadbError> {
         // debug version with content to compare against
-        let mut hasher = std::collections::hash_map::DefaultHasher::new();
-        let a: Vec<String> = table.read(*self, cipher);
+        let a: Vec<String> = table.read(*self, cipher)?;
 
-        let mut b: Vec<String> = Vec::with_capacity(1);
-        b.push(String::from(test_con));
+        let b: Vec<String> = vec![String::from(test_con)];
 
         println!("actual Row: {:?}, test Row: {:?}", a, b); // print unhashed contents
 
         assert_eq!(a, b);
 
-        b.hash(&mut hasher); // finish 'b' hash
-        let res_b = hasher.finish();
-
-        hasher = std::collections::hash_map::DefaultHasher::new(); // reset hasher
-
-        a.hash(&mut hasher); // finish 'a' hash
-        let res_a = hasher.finish();
+        let res_b = format::hash_fields(&b); // 'b' hash
+        let res_a = format::hash_fields(&a); // 'a' hash
 
         assert_eq!(res_a, res_b); // check if are the same
-        hasher.finish()
+        Ok(res_a)
     }
-    /// # delete()
+    /// # append_field()
     ///
-    /// This deletes a row from a table and the hash storage.
+    /// This adds a field with the value to the end of a row and updates the hash storage.
     ///
-    /// ## Panic
+    /// ## Errors
     ///
-    /// Returns 1 if the row can't be found, if the deletion was successful, 0 is returned.
+    /// Returns `JadbError::NotFound` if the row doesn't exist and any error of [`Table::write_values()`], e.g. if the row already has all columns of the table's [`Schema`].
     ///
     /// ## Examples
     /// ```
-    /// use jadb;
     /// use aes_gcm::{Aes128Gcm, Key};
     /// use aes_gcm::aead::NewAead;
     ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
     /// let table = jadb::Table {
-    ///   path: "mytable",
+    ///   path: "mytable_row_append_field",
     ///   id: 0,
     /// };
     ///
@@ -611,39 +2127,83 @@ impl Row {
     ///   pos: 0,
     /// };
     ///
-    /// let mut hash_storage: Vec<Vec<std::collections::HashMap<String, usize>>> = vec![vec![std::collections::HashMap::new()]];
+    /// let mut hash_storage = jadb::HashStorage::new();
     ///
     /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
     ///
-    /// table.create();
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
     ///
-    /// jadb::init(table, &mut hash_storage, &cipher); // Initialize the hash storage
+    /// table.write("hi\nyou", row, &mut hash_storage, &cipher)?; // write 'hi' and 'you' in seperate fields
     ///
-    /// table.write("hi\nyou", row, &mut hash_storage, &cipher); // write 'hi' and 'you' in seperate fields
+    /// row.append_field(table, "everyone", &mut hash_storage, &cipher)?;
     ///
-    /// row.delete(table, &mut hash_storage);
+    /// assert_eq!(table.read(row, &cipher)?, vec!["hi", "you", "everyone"]);
     ///
-    /// table.delete(&mut hash_storage); // delete table afterwards
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn delete(
+    pub fn append_field(
         &self,
         table: Table,
-        hash_var: &mut Vec<Vec<std::collections::HashMap<String, usize>>>,
-    ) -> i8 {
-        let row_path = format!("{}/{}", table.path, self.pos); // create path of row
-        return if std::path::Path::new(&row_path).exists() {
-            // use it to check if row exists
-            std::fs::remove_file(row_path).expect("Couldn't delete Row."); // delete file
-            hash_var[table.id][self.pos].clear(); // and the HashMap
-            if self.pos == hash_var[table.id].len() - 1 {
-                // if id of removed row is last element
-                hash_var[table.id].pop(); // remove last element
-            }
-            0
-        } else {
-            println!("Row doesn't exist at {}", row_path);
-            1
-        };
+        value: impl Into<Value>,
+        hash_var: &mut HashStorage,
+        cipher: &dyn RowCipher,
+    ) -> Result<(), JadbError> {
+        let mut with_field = table.read_values(*self, cipher)?; // read contents without field
+        with_field.push(value.into());
+        table.write_values(&with_field, *self, hash_var, cipher) // rewrite row with field, this updates the hash storage
+    }
+    /// # delete()
+    ///
+    /// This deletes a row from a table and the hash storage.
+    ///
+    /// The deletion is appended to the table's segment files, the old contents of the row are removed from the disk when the table is compacted,
+    /// see [`Table::compact()`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NotFound` if the row doesn't exist.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_row_delete",
+    ///   id: 0,
+    /// };
+    ///
+    /// let row = jadb::Row {
+    ///   pos: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.write("hi\nyou", row, &mut hash_storage, &cipher)?; // write 'hi' and 'you' in seperate fields
+    ///
+    /// row.delete(table, &mut hash_storage)?;
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn delete(&self, table: Table, hash_var: &mut HashStorage) -> Result<(), JadbError> {
+        if !segment::delete(table.path, self.pos)? {
+            return Err(JadbError::NotFound(format!("{}/{}", table.path, self.pos)));
+        }
+        hash_var.remove_row(table.id, *self); // and its contents in the hash storage
+        Ok(())
     }
 }
 
@@ -653,14 +2213,18 @@ impl Field {
     /// This returns the length of a field.
     /// If you plan on using the length more than once without changing the fields's content, consider saving it into a variable rather than using this function every time.
     ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::IndexOutOfRange` if the row doesn't have this field.
+    ///
     /// ## Examples
     /// ```
-    /// use jadb;
     /// use aes_gcm::{Aes128Gcm, Key};
     /// use aes_gcm::aead::NewAead;
     ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
     /// let table = jadb::Table {
-    ///   path: "mytable",
+    ///   path: "mytable_field_length",
     ///   id: 0,
     /// };
     ///
@@ -672,23 +2236,25 @@ impl Field {
     ///   pos: 0,
     /// };
     ///
-    /// let mut hash_storage: Vec<Vec<std::collections::HashMap<String, usize>>> = vec![vec![std::collections::HashMap::new()]];
+    /// let mut hash_storage = jadb::HashStorage::new();
     ///
     /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
     ///
-    /// table.create();
+    /// table.create()?;
     ///
-    /// jadb::init(table, &mut hash_storage, &cipher); // Initialize the hash storage
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
     ///
-    /// table.write("hi\nyou", row, &mut hash_storage, &cipher); // write 'hi' and 'you' in seperate fields
+    /// table.write("hi\nyou", row, &mut hash_storage, &cipher)?; // write 'hi' and 'you' in seperate fields
     ///
-    /// let length: i32 = field.length(table, row, &cipher);
+    /// let length: i32 = field.length(table, row, &cipher)?;
     ///
-    /// table.delete(&mut hash_storage); // delete table afterwards
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn length(&self, table: Table, row: Row, cipher: &Aes128Gcm) -> i32 {
-        let con = table.read(row, cipher);
-        con[self.pos].len() as i32
+    pub fn length(&self, table: Table, row: Row, cipher: &dyn RowCipher) -> Result<i32, JadbError> {
+        let con = table.read(row, cipher)?;
+        Ok(self.get(&con)?.len() as i32)
     }
     /// # shash()
     ///
@@ -696,12 +2262,12 @@ impl Field {
     ///
     /// ## Examples
     /// ```
-    /// use jadb;
     /// use aes_gcm::{Aes128Gcm, Key};
     /// use aes_gcm::aead::NewAead;
     ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
     /// let table = jadb::Table {
-    ///   path: "mytable",
+    ///   path: "mytable_field_shash",
     ///   id: 0,
     /// };
     ///
@@ -713,25 +2279,25 @@ impl Field {
     ///   pos: 0,
     /// };
     ///
-    /// let mut hash_storage: Vec<Vec<std::collections::HashMap<String, usize>>> = vec![vec![std::collections::HashMap::new()]];
+    /// let mut hash_storage = jadb::HashStorage::new();
     ///
     /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
     ///
-    /// table.create();
+    /// table.create()?;
     ///
-    /// jadb::init(table, &mut hash_storage, &cipher); // Initialize the hash storage
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
     ///
-    /// table.write("hi\nyou", row, &mut hash_storage, &cipher); // write 'hi' and 'you' in seperate fields
+    /// table.write("hi\nyou", row, &mut hash_storage, &cipher)?; // write 'hi' and 'you' in seperate fields
     ///
-    /// let hash: u64 = field.shash(table, row, &cipher);
+    /// let hash: u64 = field.shash(table, row, &cipher)?;
     ///
-    /// table.delete(&mut hash_storage); // delete table afterwards
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn shash(&self, table: Table, row: Row, cipher: &Aes128Gcm) -> u64 {
-        let mut hasher = std::collections::hash_map::DefaultHasher::new();
-        let a: Vec<String> = table.read(row, cipher);
-        a[self.pos].hash(&mut hasher);
-        hasher.finish()
+    pub fn shash(&self, table: Table, row: Row, cipher: &dyn RowCipher) -> Result<u64, JadbError> {
+        let a: Vec<String> = table.read(row, cipher)?;
+        Ok(format::hash_fields(&[self.get(&a)?]))
     }
     /// # shash_debug()
     ///
@@ -739,12 +2305,12 @@ impl Field {
     ///
     /// ## Examples
     /// ```
-    /// use jadb;
     /// use aes_gcm::{Aes128Gcm, Key};
     /// use aes_gcm::aead::NewAead;
     ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
     /// let table = jadb::Table {
-    ///   path: "mytable",
+    ///   path: "mytable_field_shash_debug",
     ///   id: 0,
     /// };
     ///
@@ -756,59 +2322,182 @@ impl Field {
     ///   pos: 0,
     /// };
     ///
-    /// let mut hash_storage: Vec<Vec<std::collections::HashMap<String, usize>>> = vec![vec![std::collections::HashMap::new()]];
+    /// let mut hash_storage = jadb::HashStorage::new();
     ///
     /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
     ///
-    /// table.create();
+    /// table.create()?;
     ///
-    /// jadb::init(table, &mut hash_storage, &cipher); // Initialize the hash storage
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
     ///
-    /// table.write("hey", row, &mut hash_storage, &cipher); // write 'hi' and 'you' in seperate fields
+    /// table.write("hey", row, &mut hash_storage, &cipher)?; // write 'hey' in a single field
     ///
-    /// let hash: u64 = field.shash_debug(table, row, "hey", &cipher);
+    /// let hash: u64 = field.shash_debug(table, row, "hey", &cipher)?;
     ///
-    /// table.delete(&mut hash_storage); // delete table afterwards
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn shash_debug(&self, table: Table, row: Row, test_con: &str, cipher: &Aes128Gcm) -> u64 {
+    pub fn shash_debug(
+        &self,
+        table: Table,
+        row: Row,
+        test_con: &str,
+        cipher: &dyn RowCipher,
+    ) -> Result<u64, JadbError> {
         // debug version with content to compare against
-        let mut hasher = std::collections::hash_map::DefaultHasher::new();
-        let a: Vec<String> = table.read(row, cipher);
+        let a: Vec<String> = table.read(row, cipher)?;
 
-        let mut b: Vec<String> = Vec::with_capacity(1);
-        b.push(String::from(test_con));
+        let b: Vec<String> = vec![String::from(test_con)];
 
-        println!("actual Field: {:?}, test Field: {:?}", a[self.pos], b[0]); // print unhashed contents
+        println!("actual Field: {:?}, test Field: {:?}", self.get(&a)?, b[0]); // print unhashed contents
 
         assert_eq!(a, b);
 
-        b[0].hash(&mut hasher); // finish 'b' hash
-        let res_b = hasher.finish();
-
-        hasher = std::collections::hash_map::DefaultHasher::new(); // reset hasher
-
-        a[self.pos].hash(&mut hasher); // finish 'a' hash
-        let res_a = hasher.finish();
+        let res_b = format::hash_fields(&b[..1]); // 'b' hash
+        let res_a = format::hash_fields(&[self.get(&a)?]); // 'a' hash
 
         assert_eq!(res_a, res_b); // check if are the same
-        hasher.finish()
+        Ok(res_a)
+    }
+    /// # set()
+    ///
+    /// This replaces the value of a field and updates the hash storage. The other fields of the row keep their values.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NotFound` if the row doesn't exist, `JadbError::IndexOutOfRange` if the row doesn't have this field
+    /// and any error of [`Table::write_values()`], e.g. if the value doesn't match the field's column.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_field_set",
+    ///   id: 0,
+    /// };
+    ///
+    /// let row = jadb::Row {
+    ///   pos: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.write("hi\nyou", row, &mut hash_storage, &cipher)?; // write 'hi' and 'you' in seperate fields
+    ///
+    /// let field = jadb::Field {
+    ///   pos: 0,
+    /// };
+    ///
+    /// field.set(table, row, 42, &mut hash_storage, &cipher)?;
+    ///
+    /// assert_eq!(table.read_values(row, &cipher)?, vec![jadb::Value::Int(42), jadb::Value::from("you")]);
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set(
+        &self,
+        table: Table,
+        row: Row,
+        value: impl Into<Value>,
+        hash_var: &mut HashStorage,
+        cipher: &dyn RowCipher,
+    ) -> Result<(), JadbError> {
+        let mut with_field = table.read_values(row, cipher)?; // read contents with old field
+        self.get(&with_field)?; // check the field exists
+        with_field[self.pos] = value.into();
+        table.write_values(&with_field, row, hash_var, cipher) // rewrite row with new field, this updates the hash storage
+    }
+    /// # insert()
+    ///
+    /// This inserts a new field with the value at the position of this field and updates the hash storage.
+    /// The field at this position and all after it move to the next position. A field can be inserted right after the last one.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::NotFound` if the row doesn't exist, `JadbError::IndexOutOfRange` if the row has less fields than the position
+    /// and any error of [`Table::write_values()`], e.g. if the row doesn't match the table's [`Schema`] afterwards.
+    ///
+    /// ## Examples
+    /// ```
+    /// use aes_gcm::{Aes128Gcm, Key};
+    /// use aes_gcm::aead::NewAead;
+    ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
+    /// let table = jadb::Table {
+    ///   path: "mytable_field_insert",
+    ///   id: 0,
+    /// };
+    ///
+    /// let row = jadb::Row {
+    ///   pos: 0,
+    /// };
+    ///
+    /// let mut hash_storage = jadb::HashStorage::new();
+    ///
+    /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+    ///
+    /// table.create()?;
+    ///
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
+    ///
+    /// table.write("hi\nyou", row, &mut hash_storage, &cipher)?; // write 'hi' and 'you' in seperate fields
+    ///
+    /// jadb::Field { pos: 1 }.insert(table, row, "there", &mut hash_storage, &cipher)?;
+    ///
+    /// assert_eq!(table.read(row, &cipher)?, vec!["hi", "there", "you"]);
+    ///
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert(
+        &self,
+        table: Table,
+        row: Row,
+        value: impl Into<Value>,
+        hash_var: &mut HashStorage,
+        cipher: &dyn RowCipher,
+    ) -> Result<(), JadbError> {
+        let mut with_field = table.read_values(row, cipher)?; // read contents without field
+        if self.pos > with_field.len() {
+            return Err(JadbError::IndexOutOfRange {
+                index: self.pos,
+                len: with_field.len(),
+            });
+        }
+        with_field.insert(self.pos, value.into());
+        table.write_values(&with_field, row, hash_var, cipher) // rewrite row with field, this updates the hash storage
     }
     /// # delete()
     ///
     /// This deletes a field from a row and the hash storage.
+    /// In a table with a [`Schema`] the columns keep their positions, so the field is set to the default of its column instead.
     ///
-    /// ## Panic
+    /// ## Errors
     ///
-    /// Returns 1 if the field can't be found, if the deletion was successful, 0 is returned.
+    /// Returns `JadbError::NotFound` if the row doesn't exist, `JadbError::IndexOutOfRange` if the row doesn't have this field
+    /// and `JadbError::SchemaViolation` if its column has no default and isn't nullable.
     ///
     /// ## Examples
     /// ```
-    /// use jadb;
     /// use aes_gcm::{Aes128Gcm, Key};
     /// use aes_gcm::aead::NewAead;
     ///
+    /// # fn main() -> Result<(), jadb::JadbError> {
     /// let table = jadb::Table {
-    ///   path: "mytable",
+    ///   path: "mytable_field_delete",
     ///   id: 0,
     /// };
     ///
@@ -820,35 +2509,50 @@ impl Field {
     ///   pos: 0,
     /// };
     ///
-    /// let mut hash_storage: Vec<Vec<std::collections::HashMap<String, usize>>> = vec![vec![std::collections::HashMap::new()]];
+    /// let mut hash_storage = jadb::HashStorage::new();
     ///
     /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
     ///
-    /// table.create();
+    /// table.create()?;
     ///
-    /// jadb::init(table, &mut hash_storage, &cipher); // Initialize the hash storage
+    /// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
     ///
-    /// table.write("hi\nyou", row, &mut hash_storage, &cipher); // write 'hi' and 'you' in seperate fields
+    /// table.write("hi\nyou", row, &mut hash_storage, &cipher)?; // write 'hi' and 'you' in seperate fields
     ///
-    /// field.delete(table, row, &mut hash_storage, &cipher);
+    /// field.delete(table, row, &mut hash_storage, &cipher)?;
     ///
-    /// table.delete(&mut hash_storage); // delete table afterwards
+    /// table.delete(&mut hash_storage)?; // delete table afterwards
+    /// # Ok(())
+    /// # }
     /// ```
     pub fn delete(
         &self,
         table: Table,
         row: Row,
-        hash_var: &mut Vec<Vec<std::collections::HashMap<String, usize>>>,
-        cipher: &Aes128Gcm,
-    ) -> i8 {
-        let mut wo_field = table.read(row, cipher); // read contents with field
-        println!("whole table {:?} self pos {}", wo_field, self.pos);
-        let to_delete = wo_field[self.pos].clone(); // save content to be deleted
-        println!("to_delete {}", to_delete);
-        wo_field.remove(self.pos); // remove it from the string
-        hash_var[table.id][row.pos].remove(&*to_delete); // and the HashMap
-        let wo_field_str: &str = &wo_field.join("\n"); // make it into one string
-        table.write(wo_field_str, row, hash_var, cipher) // rewrite row without field
+        hash_var: &mut HashStorage,
+        cipher: &dyn RowCipher,
+    ) -> Result<(), JadbError> {
+        let mut wo_field = table.read_values(row, cipher)?; // read contents with field
+        self.get(&wo_field)?; // check the field exists
+        let wo_field = match table.schema()? {
+            Some(schema) => {
+                let mut values: Vec<Option<Value>> = wo_field.into_iter().map(Some).collect();
+                values[self.pos] = None; // use the default
+                schema.conform(values)?
+            }
+            None => {
+                wo_field.remove(self.pos); // remove it, the other values keep their types
+                wo_field
+            }
+        };
+        table.write_values(&wo_field, row, hash_var, cipher) // rewrite row without field, this updates the hash storage
+    }
+    fn get<'a, T>(&self, fields: &'a [T]) -> Result<&'a T, JadbError> {
+        // get content of this field or an error if the row is too short
+        fields.get(self.pos).ok_or(JadbError::IndexOutOfRange {
+            index: self.pos,
+            len: fields.len(),
+        })
     }
 }
 
@@ -856,84 +2560,117 @@ impl Field {
 ///
 /// This functions initializes a table. The tables contents hashes are put into the hash storage.
 ///
+/// The contents are taken from the table's encrypted index file `index.jadb`, so only rows which changed since they were indexed
+/// have to be decrypted. Every row is checked against a fingerprint of its record in the table's segment files, and the index file is rebuilt if it is missing,
+/// was written with another key or contains rows which were changed, restored from a backup or deleted.
+/// The values are indexed with the [`IndexOptions`] of the table. If it has a full-text index, the words of its fields are indexed as well.
+///
+/// ## Errors
+///
+/// Returns `JadbError::NotFound` if the table doesn't exist, `JadbError::UnsupportedFormat` if it was written by a newer version of jadb, `JadbError::TableIdMismatch` if the table was created with another id,
+/// `JadbError::CipherMismatch` if the table is encrypted with another algorithm than the cipher's and `JadbError::Corrupt` if the table directory contains a file that isn't a row.
+/// Tables created with [`Algorithm::Plaintext`] can be initialized with any cipher.
+///
 /// ## Examples
 /// ```
-/// use jadb;
 /// use aes_gcm::{Aes128Gcm, Key};
 /// use aes_gcm::aead::NewAead;
 ///
+/// # fn main() -> Result<(), jadb::JadbError> {
 /// let table = jadb::Table {
-///   path: "mytable",
+///   path: "mytable_init",
 ///   id: 0,
 /// };
 ///
-/// let mut hash_storage: Vec<Vec<std::collections::HashMap<String, usize>>> = vec![vec![std::collections::HashMap::new()]];
+/// let mut hash_storage = jadb::HashStorage::new();
 ///
 /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
 ///
-/// table.create();
+/// table.create()?;
 ///
-/// jadb::init(table, &mut hash_storage, &cipher);
+/// jadb::init(table, &mut hash_storage, &cipher)?;
 ///
-/// table.delete(&mut hash_storage); // delete table afterwards
+/// table.delete(&mut hash_storage)?; // delete table afterwards
+/// # Ok(())
+/// # }
 /// ```
 pub fn init(
     table: Table,
-    hash_var: &mut Vec<Vec<std::collections::HashMap<String, usize>>>,
-    cipher: &Aes128Gcm,
-) -> i8 {
-    if hash_var.len() < table.id {
-        // if table hash var is too small
-        hash_var.resize(table.id, vec![std::collections::HashMap::new()]); // resize
-    }
-    let paths = std::fs::read_dir(table.path).expect("Couldn't read table directory"); // read dir contents
-    let mut strpaths: Vec<String> = Vec::with_capacity(100); // assuming there are 100 rows. will be only reallocated if number is higher
-    for path in paths {
-        strpaths.push(path.unwrap().path().display().to_string()); // put them into a string vector
-    }
-    strpaths.shrink_to_fit(); // free up unused memory space
-    if hash_var[table.id].len() < strpaths.len() {
-        // if row hash var is too small
-        hash_var[table.id].resize(strpaths.len() + 1, std::collections::HashMap::new());
-        // resize
-    }
-    for i in 0..strpaths.len() {
-        if !strpaths[i].contains("info.jadb") {
-            // if is a row file
-            let row_num = strpaths[i]
-                .split("/")
-                .last()
-                .expect("Couldn't get Row name from path");
-            let curr_row = Row {
-                pos: row_num.parse::<usize>().unwrap(),
-            };
-            let con: Vec<String> = table.read(curr_row, cipher);
-            for j in 0..con.len() {
-                hash_var[table.id][i].insert(con[j].clone(), j); // add them to hash table
-            }
+    hash_var: &mut HashStorage,
+    cipher: &dyn RowCipher,
+) -> Result<(), JadbError> {
+    if !std::path::Path::new(table.path).is_dir() {
+        return Err(JadbError::NotFound(table.path.to_string()));
+    }
+    format::check_version(info::value(table.path, "format version")?)?; // can this version read the table?
+    if let Some(id) = info::value(table.path, "id")? {
+        // rows are encrypted for this id, so it must not change
+        if id != table.id.to_string() {
+            return Err(JadbError::TableIdMismatch {
+                expected: table.id,
+                found: id,
+            });
         }
     }
-    0
+    let cipher = table.row_cipher(cipher)?; // the algorithm of the table is in its info file
+    let (mut saved, records) = match index_file::load(table, cipher) {
+        Some(loaded) => (loaded.entries, loaded.records),
+        None => (std::collections::BTreeMap::new(), 0), // no usable index file, rebuild it
+    };
+    let mut entries = std::collections::BTreeMap::new();
+    let mut changed = false;
+    for (pos, fingerprint) in segment::fingerprints(table.path)? {
+        let curr_row = Row { pos };
+        let entry = match saved.remove(&curr_row.pos) {
+            Some(entry) if entry.fingerprint == fingerprint => entry, // row didn't change since it was saved
+            _ => {
+                changed = true; // new, changed or restored row, read it again
+                index_file::Entry {
+                    fingerprint,
+                    fields: table.read_values(curr_row, cipher)?,
+                }
+            }
+        };
+        entries.insert(curr_row.pos, entry);
+    }
+    if changed || !saved.is_empty() || records != entries.len() {
+        index_file::save(table, cipher, &entries)?; // drop stale and replaced records
+    }
+    let full_text = match info::value(table.path, "full text")? {
+        Some(options) => Some(FullText::from_info(&options)?),
+        None => None,
+    };
+    let index_options = match info::value(table.path, "index")? {
+        Some(options) => IndexOptions::from_info(&options)?,
+        None => IndexOptions::default(),
+    };
+    hash_var.remove_table(table.id); // forget contents of an earlier init
+    hash_var.set_full_text(table.id, full_text);
+    hash_var.set_index_options(table.id, index_options);
+    for (pos, entry) in entries {
+        hash_var.set_row(table.id, Row { pos }, entry.fields); // add them to hash storage
+    }
+    Ok(())
 }
 
 /// # search()
 ///
 /// Using this you can search all tables for a string.
 ///
-/// The hash storage of all tables is searched for the hash of the String that aims to be found.
+/// The term is looked up in the hash storage, so no row has to be read, no matter how many tables there are.
 ///
 /// ## Panic
 ///
-/// If the term isn't found, a empty vector will be returned. Else a vector consisting of the table id, the row position and the field position will be returned.
+/// Every field of every table containing exactly the term is returned as a [`Hit`], ordered by table and row. If the term isn't found, a empty vector is returned.
 ///
 /// ## Examples
 /// ```
-/// use jadb;
 /// use aes_gcm::{Aes128Gcm, Key};
 /// use aes_gcm::aead::NewAead;
 ///
+/// # fn main() -> Result<(), jadb::JadbError> {
 /// let table = jadb::Table {
-///   path: "mytable",
+///   path: "mytable_search_all",
 ///   id: 0,
 /// };
 ///
@@ -941,54 +2678,170 @@ pub fn init(
 ///   pos: 0,
 /// };
 ///
-/// let mut hash_storage: Vec<Vec<std::collections::HashMap<String, usize>>> = vec![vec![std::collections::HashMap::new()]];
+/// let mut hash_storage = jadb::HashStorage::new();
 ///
 /// let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
 ///
-/// table.create();
+/// table.create()?;
 ///
-/// jadb::init(table, &mut hash_storage, &cipher); // Initialize the hash storage
+/// jadb::init(table, &mut hash_storage, &cipher)?; // Initialize the hash storage
 ///
-/// table.write("hi\nyou", row, &mut hash_storage, &cipher); // write 'hi' and 'you' in seperate fields
+/// table.write("hi\nyou", row, &mut hash_storage, &cipher)?; // write 'hi' and 'you' in seperate fields
 ///
-/// let location: Vec<usize> = jadb::search(String::from("hi"), &mut hash_storage);
+/// let hits: Vec<jadb::Hit> = jadb::search(String::from("hi"), &hash_storage);
 ///
-/// table.delete(&mut hash_storage); // delete table afterwards
+/// assert_eq!(hits, vec![jadb::Hit { table: 0, row, field: jadb::Field { pos: 0 } }]);
+///
+/// table.delete(&mut hash_storage)?; // delete table afterwards
+/// # Ok(())
+/// # }
 /// ```
-pub fn search(
-    term: String,
-    hash_var: &Vec<Vec<std::collections::HashMap<String, usize>>>,
-) -> Vec<usize> {
-    for i in 0..hash_var.len() {
-        // iterate through whole hash array
-        for j in 0..hash_var[i].len() {
-            // iterate through every table
-            return match hash_var[i][j].get(&term) {
-                Some(result) => vec![i, j, *result],
-                None => vec![],
-            };
-        }
+pub fn search(term: String, hash_var: &HashStorage) -> Vec<Hit> {
+    hash_var.get(&term)
+}
+
+/// # search_prefix()
+///
+/// Using this you can search all tables for fields starting with a string. See [`Table::search_prefix()`].
+///
+/// Every field of every table starting with the prefix is returned as a [`Hit`], ordered by table, row and field.
+pub fn search_prefix(prefix: String, hash_var: &HashStorage) -> Vec<Hit> {
+    hash_var
+        .find(None, |options| {
+            Ok(hash_var.values_with_prefix(&options.normalize(&prefix)))
+        })
+        .unwrap_or_default()
+}
+
+/// # search_contains()
+///
+/// Using this you can search all tables for fields containing a string. See [`Table::search_contains()`].
+///
+/// Every field of every table containing the string is returned as a [`Hit`], ordered by table, row and field.
+pub fn search_contains(text: String, hash_var: &HashStorage) -> Vec<Hit> {
+    hash_var
+        .find(None, |options| {
+            Ok(hash_var.values_containing(&options.normalize(&text)))
+        })
+        .unwrap_or_default()
+}
+
+/// # search_regex()
+///
+/// Using this you can search all tables for fields matching a regular expression. See [`Table::search_regex()`].
+///
+/// Every field of every table matching the expression is returned as a [`Hit`], ordered by table, row and field.
+///
+/// ## Errors
+///
+/// Returns `JadbError::InvalidRegex` if the pattern isn't a valid regular expression.
+pub fn search_regex(pattern: String, hash_var: &HashStorage) -> Result<Vec<Hit>, JadbError> {
+    hash_var.find(None, |options| {
+        hash_var.values_matching(&regex_for(&pattern, options))
+    })
+}
+
+fn regex_for(pattern: &str, options: IndexOptions) -> String {
+    // the pattern for values indexed with the options, lowercasing it would change escapes like \W
+    match options.case_folding {
+        true => options.normalization.apply(&format!("(?i){}", pattern)),
+        false => options.normalization.apply(pattern),
+    }
+}
+
+/// # search_fuzzy()
+///
+/// Using this you can search all tables for fields which are at most `max_distance` edits away from a string. See [`Table::search_fuzzy()`].
+///
+/// Every field of every table within the distance is returned as a [`Hit`], ordered by table, row and field.
+pub fn search_fuzzy(term: String, max_distance: usize, hash_var: &HashStorage) -> Vec<Hit> {
+    hash_var
+        .find(None, |options| {
+            Ok(fuzzy_values(
+                hash_var,
+                &options.normalize(&term),
+                max_distance,
+            ))
+        })
+        .unwrap_or_default()
+}
+
+fn fuzzy_values<'s>(hash_var: &'s HashStorage, term: &str, max_distance: usize) -> Vec<&'s str> {
+    hash_var
+        .values_within(term, max_distance)
+        .into_iter()
+        .map(|(value, _)| value)
+        .collect()
+}
+
+/// # search_text()
+///
+/// Using this you can search the words of all tables with a full-text index. See [`Table::search_text()`].
+///
+/// Every matching field is returned as a [`TextHit`], ranked by its BM25 score with the best match first. Scores are computed per table.
+pub fn search_text(query: String, hash_var: &HashStorage) -> Vec<TextHit> {
+    hash_var.search_text(&query, None)
+}
+
+fn to_values(version: Option<u8>, con: &[u8]) -> Result<Vec<Value>, JadbError> {
+    // decode decrypted row, older format versions contain text fields separated by newlines
+    if version >= Some(5) {
+        return value::decode_row(con);
+    }
+    let con_split = split_by_delim(con, &10u8);
+
+    let mut final_array: Vec<Value> = Vec::with_capacity(con_split.len());
+    for field in con_split {
+        final_array.push(Value::Text(std::str::from_utf8(field)?.to_string()));
     }
-    vec![]
+    Ok(final_array)
 }
 
+/// # split_by_delim()
+///
+/// This splits a slice at every delimiter, like [`slice::split()`]. Every field between two delimiters is returned,
+/// including empty ones at the start and end, so joining them with the delimiter gives back the input. An empty input has no fields.
+///
+/// Rows written by older versions of jadb are split into their fields with it. Since format version 5, the fields of a row are saved
+/// with their length instead, so they may contain newlines and any bytes, see [`Table::write_values()`].
+///
+/// ## Examples
+/// ```
+/// assert_eq!(jadb::split_by_delim(b"\nhi\n\nyou\n", &b'\n'), vec![&b""[..], b"hi", b"", b"you", b""]);
+/// ```
 pub fn split_by_delim<'a, T>(input: &'a [T], delim: &T) -> Vec<&'a [T]>
 where
     T: PartialEq<T>,
 {
-    let elements = input.iter().enumerate();
-    let (k, mut split_vectors) =
-        elements.fold((0, vec![]), |(prev_iterator, mut split_vectors), (curr_iterator, curr_element)| {
-            if curr_element == delim && curr_iterator > 0 {
-                let l = if &input[prev_iterator] == delim { prev_iterator + 1 } else { prev_iterator };
-                split_vectors.push(&input[l..curr_iterator]);
-                return (curr_iterator, split_vectors);
-            }
-            (prev_iterator, split_vectors)
-        });
-    if !input.is_empty() {
-        let m = if &input[k] == delim { k + 1 } else { k };
-        split_vectors.push(&input[m..]);
+    if input.is_empty() {
+        return vec![];
+    }
+    input.split(|element| element == delim).collect()
+}
+
+/// Replaces the file at the path with the content at once. It is written to a temporary file, which is synced before it is renamed,
+/// so a crash leaves either the old or the new file.
+pub(crate) fn replace_file(path: &str, content: &[u8]) -> Result<(), JadbError> {
+    let tmp_path = format!("{}.tmp", path);
+    let mut file = std::fs::File::create(&tmp_path)?;
+    std::io::Write::write_all(&mut file, content)?;
+    file.sync_all()?;
+    std::fs::rename(tmp_path, path)?;
+    match std::path::Path::new(path)
+        .parent()
+        .and_then(|dir| dir.to_str())
+    {
+        Some(dir) if !dir.is_empty() => sync_dir(dir),
+        _ => sync_dir("."),
     }
-    split_vectors
+}
+
+/// Syncs the directory, so files created, renamed or removed in it are still there after a crash.
+pub(crate) fn sync_dir(dir: &str) -> Result<(), JadbError> {
+    // directories can only be opened as files on unix
+    #[cfg(unix)]
+    std::fs::File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
 }
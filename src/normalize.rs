@@ -0,0 +1,96 @@
+// normalization of indexed values
+//
+// The hash storage indexes every value of a table in the normal form of the table's index options, and search terms are
+// normalized the same way, so e.g. a precomposed and a decomposed `é` find each other. The rows keep the values as written.
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::JadbError;
+
+/// # Normalization
+///
+/// The Unicode normalization form values are indexed in.
+///
+/// ## Examples
+/// ```
+/// assert_eq!(jadb::Normalization::Nfc.apply("cafe\u{301}"), "caf\u{e9}");
+/// assert_eq!(jadb::Normalization::Nfkc.apply("\u{fb01}le"), "file"); // the ligature is split
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Normalization {
+    /// Values are indexed as written.
+    #[default]
+    None,
+    /// Canonical composition, so composed and decomposed characters are the same.
+    Nfc,
+    /// Compatibility composition, which also replaces e.g. ligatures and full-width letters with their plain forms.
+    Nfkc,
+}
+
+impl Normalization {
+    /// Returns the value in the normalization form.
+    pub fn apply(&self, value: &str) -> String {
+        match self {
+            Normalization::None => value.to_string(),
+            Normalization::Nfc => value.nfc().collect(),
+            Normalization::Nfkc => value.nfkc().collect(),
+        }
+    }
+}
+
+/// # IndexOptions
+///
+/// How the values of a table are indexed in the hash storage, see [`Table::set_index_options()`](crate::Table::set_index_options()).
+/// By default values are indexed as written, so searches are case-sensitive and compare the exact characters.
+///
+/// ## Examples
+/// ```
+/// let options = jadb::IndexOptions {
+///     case_folding: true,
+///     normalization: jadb::Normalization::Nfc,
+/// };
+///
+/// assert_eq!(options.normalize("Cafe\u{301}"), options.normalize("caf\u{e9}"));
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexOptions {
+    /// Index values lowercased, so searches ignore case.
+    pub case_folding: bool,
+    /// Index values in a Unicode normalization form.
+    pub normalization: Normalization,
+}
+
+impl IndexOptions {
+    /// Returns the value as it is indexed.
+    pub fn normalize(&self, value: &str) -> String {
+        match self.case_folding {
+            true => self.normalization.apply(&value.to_lowercase()),
+            false => self.normalization.apply(value),
+        }
+    }
+    /// Value of the `index` line in the info file.
+    pub(crate) fn info(&self) -> String {
+        let mut options = vec![];
+        if self.case_folding {
+            options.push("case folding");
+        }
+        match self.normalization {
+            Normalization::None => {}
+            Normalization::Nfc => options.push("nfc"),
+            Normalization::Nfkc => options.push("nfkc"),
+        }
+        options.join(", ")
+    }
+    pub(crate) fn from_info(info: &str) -> Result<IndexOptions, JadbError> {
+        let mut options = IndexOptions::default();
+        for option in info.split(", ").filter(|option| !option.is_empty()) {
+            match option {
+                "case folding" => options.case_folding = true,
+                "nfc" => options.normalization = Normalization::Nfc,
+                "nfkc" => options.normalization = Normalization::Nfkc,
+                _ => return Err(JadbError::UnsupportedFormat(format!("index {}", info))),
+            }
+        }
+        Ok(options)
+    }
+}
@@ -0,0 +1,333 @@
+// the optional schema of a table
+//
+// Every column is saved as a line of the info file, `column <position>: <name> <type>[ null][ default <value>]`.
+// Defaults are saved in their text form, see `Value`, with `\` and line breaks escaped.
+
+use chrono::{DateTime, Utc};
+
+use crate::{info, Field, JadbError, Value};
+
+/// # ColumnType
+///
+/// The type of the values of a column.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    Bool,
+    Int,
+    /// Floats, integers are converted when they are written.
+    Float,
+    Text,
+    Bytes,
+    Timestamp,
+}
+
+impl ColumnType {
+    /// Name of the type in the info file and in errors.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColumnType::Bool => "bool",
+            ColumnType::Int => "int",
+            ColumnType::Float => "float",
+            ColumnType::Text => "text",
+            ColumnType::Bytes => "bytes",
+            ColumnType::Timestamp => "timestamp",
+        }
+    }
+    fn from_name(name: &str) -> Option<ColumnType> {
+        [
+            ColumnType::Bool,
+            ColumnType::Int,
+            ColumnType::Float,
+            ColumnType::Text,
+            ColumnType::Bytes,
+            ColumnType::Timestamp,
+        ]
+        .into_iter()
+        .find(|column_type| column_type.name() == name)
+    }
+    /// Returns the value of this type from its text form, `None` if it isn't one.
+    pub fn parse(&self, text: &str) -> Option<Value> {
+        Some(match self {
+            ColumnType::Bool => Value::Bool(text.parse().ok()?),
+            ColumnType::Int => Value::Int(text.parse().ok()?),
+            ColumnType::Float => Value::Float(text.parse().ok()?),
+            ColumnType::Text => Value::Text(text.to_string()),
+            ColumnType::Bytes => {
+                if !text.len().is_multiple_of(2) || !text.is_ascii() {
+                    return None;
+                }
+                Value::Bytes(
+                    (0..text.len())
+                        .step_by(2)
+                        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+                        .collect::<Option<_>>()?,
+                )
+            }
+            ColumnType::Timestamp => {
+                Value::Timestamp(DateTime::parse_from_rfc3339(text).ok()?.with_timezone(&Utc))
+            }
+        })
+    }
+    /// Returns the value as this type, `None` if it has another one.
+    fn convert(&self, value: Value) -> Option<Value> {
+        match (self, value) {
+            (ColumnType::Bool, value @ Value::Bool(_)) => Some(value),
+            (ColumnType::Int, value @ Value::Int(_)) => Some(value),
+            (ColumnType::Float, value @ Value::Float(_)) => Some(value),
+            (ColumnType::Float, Value::Int(value)) => Some(Value::Float(value as f64)),
+            (ColumnType::Text, value @ Value::Text(_)) => Some(value),
+            (ColumnType::Bytes, value @ Value::Bytes(_)) => Some(value),
+            (ColumnType::Timestamp, value @ Value::Timestamp(_)) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// # Column
+///
+/// A named, typed column of a table with a schema.
+///
+/// ## Examples
+/// ```
+/// let column = jadb::Column {
+///     name: String::from("age"),
+///     column_type: jadb::ColumnType::Int,
+///     nullable: false,
+///     default: Some(jadb::Value::Int(0)),
+/// };
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+    /// Name of the column, made of letters, digits and `_`.
+    pub name: String,
+    pub column_type: ColumnType,
+    /// Whether the column may be [`Value::Null`].
+    pub nullable: bool,
+    /// Value of the column if none is written, otherwise it is null if the column is nullable and an error if not.
+    pub default: Option<Value>,
+}
+
+/// # Schema
+///
+/// The columns of a table, see [`Table::set_schema()`](crate::Table::set_schema()). Field `i` of every row is column `i`.
+///
+/// ## Examples
+/// ```
+/// let schema = jadb::Schema {
+///     columns: vec![
+///         jadb::Column { name: String::from("name"), column_type: jadb::ColumnType::Text, nullable: false, default: None },
+///         jadb::Column { name: String::from("age"), column_type: jadb::ColumnType::Int, nullable: true, default: None },
+///     ],
+/// };
+///
+/// assert_eq!(schema.field("age"), Some(jadb::Field { pos: 1 }));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Schema {
+    pub columns: Vec<Column>,
+}
+
+impl Schema {
+    /// Returns the field of the column with the given name.
+    pub fn field(&self, name: &str) -> Option<Field> {
+        self.columns
+            .iter()
+            .position(|column| column.name == name)
+            .map(|pos| Field { pos })
+    }
+    /// Checks that the column names are valid and unique and the defaults match their columns.
+    pub(crate) fn check(&self) -> Result<(), JadbError> {
+        for (i, column) in self.columns.iter().enumerate() {
+            if column.name.is_empty()
+                || !column.name.chars().all(|c| c.is_alphanumeric() || c == '_')
+            {
+                return Err(JadbError::InvalidName(column.name.clone()));
+            }
+            if self.columns[..i]
+                .iter()
+                .any(|other| other.name == column.name)
+            {
+                return Err(JadbError::AlreadyExists(column.name.clone()));
+            }
+            if let Some(default) = &column.default {
+                column.check(default.clone())?;
+            }
+        }
+        Ok(())
+    }
+    /// Returns the values of a row with the defaults of missing values filled in and integers in float columns converted,
+    /// or an error if they don't match the schema. `None` is a missing value.
+    pub(crate) fn conform(&self, values: Vec<Option<Value>>) -> Result<Vec<Value>, JadbError> {
+        if values.len() > self.columns.len() {
+            return Err(JadbError::SchemaViolation(format!(
+                "{} fields for {} columns",
+                values.len(),
+                self.columns.len()
+            )));
+        }
+        let mut values = values.into_iter();
+        self.columns
+            .iter()
+            .map(|column| match values.next().flatten() {
+                Some(value) => column.check(value),
+                None => match (&column.default, column.nullable) {
+                    (Some(default), _) => Ok(default.clone()),
+                    (None, true) => Ok(Value::Null),
+                    (None, false) => Err(JadbError::SchemaViolation(format!(
+                        "{}: no value",
+                        column.name
+                    ))),
+                },
+            })
+            .collect()
+    }
+    /// Returns the value of the field at the position written as text, see [`Table::write()`](crate::Table::write()). Empty fields of columns which aren't text are missing.
+    pub(crate) fn parse(&self, pos: usize, text: &str) -> Result<Option<Value>, JadbError> {
+        let column = match self.columns.get(pos) {
+            Some(column) => column,
+            None => return Ok(Some(Value::Text(text.to_string()))), // too many fields, found by conform
+        };
+        if text.is_empty() && column.column_type != ColumnType::Text {
+            return Ok(None);
+        }
+        match column.column_type.parse(text) {
+            Some(value) => Ok(Some(value)),
+            None => Err(JadbError::SchemaViolation(format!(
+                "{}: {:?} isn't a {}",
+                column.name,
+                text,
+                column.column_type.name()
+            ))),
+        }
+    }
+    /// Returns the schema saved in the info file of the table, if it has one.
+    pub(crate) fn read(table_path: &str) -> Result<Option<Schema>, JadbError> {
+        let mut columns = vec![];
+        for (key, line) in info::read(table_path)? {
+            if let Some(pos) = key.strip_prefix("column ") {
+                let pos: usize = pos
+                    .parse()
+                    .map_err(|_| JadbError::Corrupt(format!("info line {}", key)))?;
+                columns.push((pos, Column::from_info(&line)?));
+            }
+        }
+        if columns.is_empty() {
+            return Ok(None);
+        }
+        columns.sort_by_key(|(pos, _)| *pos);
+        if columns.iter().enumerate().any(|(i, (pos, _))| i != *pos) {
+            return Err(JadbError::Corrupt(String::from("missing schema column")));
+        }
+        let schema = Schema {
+            columns: columns.into_iter().map(|(_, column)| column).collect(),
+        };
+        schema.check()?;
+        Ok(Some(schema))
+    }
+    /// Replaces the schema in the info file of the table, removes it if `None` is given.
+    pub(crate) fn save(table_path: &str, schema: Option<&Schema>) -> Result<(), JadbError> {
+        for (key, _) in info::read(table_path)? {
+            if key.starts_with("column ") {
+                info::remove(table_path, &key)?;
+            }
+        }
+        for (pos, column) in schema
+            .into_iter()
+            .flat_map(|schema| &schema.columns)
+            .enumerate()
+        {
+            info::set(table_path, &format!("column {}", pos), &column.info())?;
+        }
+        Ok(())
+    }
+}
+
+impl Column {
+    fn check(&self, value: Value) -> Result<Value, JadbError> {
+        // the value as saved in this column
+        if value == Value::Null {
+            return match self.nullable {
+                true => Ok(value),
+                false => Err(JadbError::SchemaViolation(format!(
+                    "{}: not nullable",
+                    self.name
+                ))),
+            };
+        }
+        let found = format!("{:?}", value);
+        self.column_type.convert(value).ok_or_else(|| {
+            JadbError::SchemaViolation(format!(
+                "{}: {} isn't a {}",
+                self.name,
+                found,
+                self.column_type.name()
+            ))
+        })
+    }
+    /// Value of the `column` line in the info file.
+    fn info(&self) -> String {
+        let mut info = format!("{} {}", self.name, self.column_type.name());
+        if self.nullable {
+            info.push_str(" null");
+        }
+        if let Some(default) = &self.default {
+            let default = default
+                .to_string()
+                .replace('\\', "\\\\")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r");
+            info.push_str(&format!(" default {}", default));
+        }
+        info
+    }
+    fn from_info(info: &str) -> Result<Column, JadbError> {
+        let unsupported = || JadbError::UnsupportedFormat(format!("column {}", info));
+        let (definition, default) = match info.split_once(" default ") {
+            Some((definition, default)) => (definition, Some(default)),
+            None => (info, None),
+        };
+        let mut words = definition.split(' ');
+        let name = words.next().ok_or_else(unsupported)?.to_string();
+        let column_type =
+            ColumnType::from_name(words.next().ok_or_else(unsupported)?).ok_or_else(unsupported)?;
+        let nullable = match words.next() {
+            Some("null") => true,
+            None => false,
+            Some(_) => return Err(unsupported()),
+        };
+        if words.next().is_some() {
+            return Err(unsupported());
+        }
+        let default = match default {
+            Some(default) => Some(
+                column_type
+                    .parse(&unescape(default))
+                    .ok_or_else(unsupported)?,
+            ),
+            None => None,
+        };
+        Ok(Column {
+            name,
+            column_type,
+            nullable,
+            default,
+        })
+    }
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        unescaped.push(match c {
+            '\\' => match chars.next() {
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some(other) => other,
+                None => break,
+            },
+            c => c,
+        });
+    }
+    unescaped
+}
@@ -0,0 +1,552 @@
+// the segment files the rows of a table are saved in
+//
+// Instead of a file per row, rows are appended to the segment files `segment-<number>.jadb` in the table directory. Every segment starts with a header:
+//
+// | Bytes | Content |
+// | ----- | ------- |
+// | 4 | magic `JADS` |
+// | 1 | segment format version |
+//
+// followed by records:
+//
+// | Bytes | Content |
+// | ----- | ------- |
+// | 4 | length of the row (u32, little endian) |
+// | 8 | row position (u64, little endian) |
+// | 1 | 0 for a written row, 1 for a deleted one |
+// | n | the row, its header with the nonce followed by the ciphertext, see `format.rs`, empty for deleted rows |
+// | 8 | checksum of the record before it (u64, little endian), see `format::checksum()` |
+//
+// A later record of a row replaces the earlier ones. Records are only appended to the segment with the highest number, a new one is started
+// when it would grow beyond `SEGMENT_LEN`. If more than half of the segments are replaced or deleted records by then, the table is compacted:
+// the last record of every row is copied to new segments and the old ones are removed.
+//
+// The offset of the last record of every row is kept in memory for every table this process uses. It is read again from the segments
+// if they changed since, e.g. because another process wrote to the table. Every access holds an exclusive lock on the file `lock.jadb`
+// in the table directory, so processes don't append over each other's records. Appended records are synced before they are used.
+// Segments are read up to the first record which isn't complete, whose checksum doesn't match or whose kind is unknown, e.g. because
+// of a crash while it was written. It and everything after it are ignored and cut off before the next record is appended.
+//
+// Up to format version 5, every row was saved in its own file named by its position. These files are still read,
+// a row is moved to the segments when it is written again or the table is migrated.
+
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::sync::{Mutex, PoisonError};
+use std::time::SystemTime;
+
+use crate::{format, index_file, JadbError};
+
+const MAGIC: &[u8; 4] = b"JADS";
+const SEGMENT_VERSION: u8 = 1;
+const HEADER_LEN: u64 = MAGIC.len() as u64 + 1;
+
+// length of a record without its row: length, position and kind before it and the checksum after it
+const FRAME_LEN: u64 = 4 + 8 + 1;
+const CHECKSUM_LEN: u64 = 8;
+
+// kinds of records
+const WRITTEN: u8 = 0;
+const DELETED: u8 = 1;
+
+/// File in the table directory which is locked while the segments are used.
+const LOCK_NAME: &str = "lock.jadb";
+
+/// Size of a segment after which a new one is started.
+const SEGMENT_LEN: u64 = 64 * 1024 * 1024;
+
+// the stores of the tables used by this process, by canonical table path, so every spelling of the path uses the same store
+static STORES: Mutex<BTreeMap<String, Store>> = Mutex::new(BTreeMap::new());
+
+/// Where the last record of a row is.
+#[derive(Copy, Clone)]
+struct Location {
+    segment: u32,
+    offset: u64,
+    len: u32, // of the row
+    checksum: u64,
+}
+
+struct Segment {
+    number: u32,
+    len: u64, // length and modification time of the file when it was read
+    modified: Option<SystemTime>,
+    end: u64, // end of the last complete record
+}
+
+/// The segments of a table and the rows in them.
+#[derive(Default)]
+struct Store {
+    segments: Vec<Segment>,
+    rows: BTreeMap<usize, Location>,
+    dead: u64, // bytes of replaced and deleted records
+}
+
+/// Returns the row at the position, `None` if the table doesn't have it.
+pub(crate) fn read(table_path: &str, pos: usize) -> Result<Option<Vec<u8>>, JadbError> {
+    with_store(table_path, |store| match store.rows.get(&pos) {
+        Some(&location) => store.read(table_path, pos, location).map(Some),
+        None => match std::fs::read(old_row_path(table_path, pos)) {
+            Ok(row) => Ok(Some(row)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(JadbError::Io(err)),
+        },
+    })
+}
+
+/// Appends the row at the position to the table and returns its fingerprint, the checksum of its record.
+pub(crate) fn write(table_path: &str, pos: usize, row: &[u8]) -> Result<u64, JadbError> {
+    with_store(table_path, |store| {
+        let checksum = store.append(table_path, pos, WRITTEN, row)?;
+        remove_old_row(table_path, pos)?; // the row is in the segments now
+        Ok(checksum)
+    })
+}
+
+/// Deletes the row at the position. Returns `false` if the table doesn't have it.
+pub(crate) fn delete(table_path: &str, pos: usize) -> Result<bool, JadbError> {
+    with_store(table_path, |store| {
+        let in_segments = store.rows.contains_key(&pos);
+        if in_segments {
+            store.append(table_path, pos, DELETED, &[])?;
+        }
+        Ok(remove_old_row(table_path, pos)? || in_segments)
+    })
+}
+
+/// Returns whether the table has a row at the position.
+pub(crate) fn exists(table_path: &str, pos: usize) -> Result<bool, JadbError> {
+    with_store(table_path, |store| {
+        Ok(store.rows.contains_key(&pos)
+            || std::path::Path::new(&old_row_path(table_path, pos)).exists())
+    })
+}
+
+/// Returns the positions of all rows of the table, in order.
+pub(crate) fn positions(table_path: &str) -> Result<Vec<usize>, JadbError> {
+    with_store(table_path, |store| {
+        let mut positions: Vec<usize> = store.rows.keys().copied().collect();
+        positions.extend(old_rows(table_path)?);
+        positions.sort_unstable();
+        positions.dedup(); // a row file may be left if writing the row was interrupted
+        Ok(positions)
+    })
+}
+
+/// Returns the fingerprints of all rows of the table by position.
+pub(crate) fn fingerprints(table_path: &str) -> Result<BTreeMap<usize, u64>, JadbError> {
+    with_store(table_path, |store| {
+        let mut fingerprints: BTreeMap<usize, u64> = store
+            .rows
+            .iter()
+            .map(|(&pos, location)| (pos, location.checksum))
+            .collect();
+        for pos in old_rows(table_path)? {
+            if let std::collections::btree_map::Entry::Vacant(entry) = fingerprints.entry(pos) {
+                entry.insert(format::file_fingerprint(&old_row_path(table_path, pos))?);
+            }
+        }
+        Ok(fingerprints)
+    })
+}
+
+/// Copies the last record of every row to new segments and removes the old ones.
+pub(crate) fn compact(table_path: &str) -> Result<(), JadbError> {
+    with_store(table_path, |store| store.compact(table_path))
+}
+
+/// Forgets the rows kept in memory for the table, e.g. because it will be deleted.
+pub(crate) fn forget(table_path: &str) {
+    if let Ok(key) = store_key(table_path) {
+        STORES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&key);
+    } // a table which doesn't exist is read again anyway, see `Store::refresh()`
+}
+
+fn store_key(table_path: &str) -> Result<String, JadbError> {
+    let path = std::fs::canonicalize(table_path).map_err(|err| match err.kind() {
+        std::io::ErrorKind::NotFound => JadbError::NotFound(table_path.to_string()),
+        _ => JadbError::Io(err),
+    })?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+fn with_store<T>(
+    table_path: &str,
+    f: impl FnOnce(&mut Store) -> Result<T, JadbError>,
+) -> Result<T, JadbError> {
+    // runs f with the store of the table, which is read again if its segments changed
+    let key = store_key(table_path)?;
+    let mut stores = STORES.lock().unwrap_or_else(PoisonError::into_inner);
+    let lock = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(format!("{}/{}", table_path, LOCK_NAME))?;
+    lock.lock()?; // unlocked when it is closed
+    let store = match stores.remove(&key) {
+        Some(mut store) => match store.refresh(table_path)? {
+            true => store,
+            false => Store::load(table_path)?,
+        },
+        None => Store::load(table_path)?,
+    };
+    let store = stores.entry(key.clone()).or_insert(store);
+    let result = f(store);
+    if result.is_err() {
+        stores.remove(&key); // may not match the segments anymore
+    }
+    result
+}
+
+impl Store {
+    fn load(table_path: &str) -> Result<Store, JadbError> {
+        if !std::path::Path::new(table_path).is_dir() {
+            return Err(JadbError::NotFound(table_path.to_string()));
+        }
+        let mut numbers = vec![];
+        for entry in std::fs::read_dir(table_path)? {
+            if let Some(number) = entry?.file_name().to_str().and_then(segment_number) {
+                numbers.push(number);
+            }
+        }
+        numbers.sort_unstable();
+        let mut store = Store::default();
+        for number in numbers {
+            store.segments.push(Segment {
+                number,
+                len: 0,
+                modified: None,
+                end: 0,
+            });
+            store.read_segment(table_path, store.segments.len() - 1)?;
+        }
+        Ok(store)
+    }
+    fn refresh(&mut self, table_path: &str) -> Result<bool, JadbError> {
+        // checks whether the segments are unchanged, only reads the new records if the last one grew.
+        // Returns false if the store has to be loaded again.
+        let next = self.segments.last().map_or(0, |segment| segment.number + 1);
+        if std::path::Path::new(&segment_path(table_path, next)).exists()
+            || !std::path::Path::new(table_path).is_dir()
+        {
+            return Ok(false);
+        }
+        let mut grown = false;
+        for (i, segment) in self.segments.iter().enumerate() {
+            let metadata = match std::fs::metadata(segment_path(table_path, segment.number)) {
+                Ok(metadata) => metadata,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+                Err(err) => return Err(JadbError::Io(err)),
+            };
+            if metadata.len() == segment.len && metadata.modified().ok() == segment.modified {
+                continue;
+            }
+            if i + 1 < self.segments.len()
+                || metadata.len() < segment.len
+                || segment.end != segment.len
+            {
+                return Ok(false);
+            }
+            grown = true; // records were appended by someone else
+        }
+        if grown {
+            self.read_segment(table_path, self.segments.len() - 1)?;
+        }
+        Ok(true)
+    }
+    fn read_segment(&mut self, table_path: &str, i: usize) -> Result<(), JadbError> {
+        // reads the records after the end of the segment
+        let path = segment_path(table_path, self.segments[i].number);
+        let from = self.segments[i].end;
+        let mut file = std::fs::File::open(&path)?;
+        let modified = file.metadata()?.modified().ok();
+        file.seek(SeekFrom::Start(from))?;
+        let mut content = vec![];
+        file.read_to_end(&mut content)?;
+        let mut at = 0;
+        if from == 0 && content.len() >= HEADER_LEN as usize {
+            // a shorter segment is empty, its header was never finished
+            if &content[..MAGIC.len()] != MAGIC {
+                return Err(JadbError::Corrupt(path));
+            }
+            if content[MAGIC.len()] > SEGMENT_VERSION {
+                return Err(JadbError::UnsupportedFormat(format!(
+                    "segment {}",
+                    content[MAGIC.len()]
+                )));
+            }
+            at = HEADER_LEN as usize;
+        }
+        while content.len() - at >= FRAME_LEN as usize {
+            let rest = &content[at..];
+            let len = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+            let record_len = record_len(len) as usize;
+            if rest.len() < record_len {
+                break; // the record wasn't finished
+            }
+            let (framed, checksum) =
+                rest[..record_len].split_at(record_len - CHECKSUM_LEN as usize);
+            let checksum = le_u64(checksum);
+            if format::checksum(framed) != checksum || framed[12] > DELETED {
+                break; // written only in part, e.g. zeros or a torn record left by a crash
+            }
+            let location = Location {
+                segment: self.segments[i].number,
+                offset: from + at as u64,
+                len,
+                checksum,
+            };
+            self.apply(le_u64(&framed[4..12]) as usize, framed[12], location);
+            at += record_len;
+        }
+        let segment = &mut self.segments[i];
+        segment.end = from + at as u64;
+        segment.len = from + content.len() as u64;
+        segment.modified = modified;
+        Ok(())
+    }
+    fn apply(&mut self, pos: usize, kind: u8, location: Location) {
+        let replaced = match kind {
+            DELETED => {
+                self.dead += record_len(location.len); // the deletion itself isn't needed after compacting
+                self.rows.remove(&pos)
+            }
+            _ => self.rows.insert(pos, location),
+        };
+        if let Some(replaced) = replaced {
+            self.dead += record_len(replaced.len);
+        }
+    }
+    fn append(
+        &mut self,
+        table_path: &str,
+        pos: usize,
+        kind: u8,
+        row: &[u8],
+    ) -> Result<u64, JadbError> {
+        let len = u32::try_from(row.len()).map_err(|_| {
+            JadbError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "row is longer than 4 GiB",
+            ))
+        })?;
+        let full = |store: &Store| {
+            store.segments.last().is_none_or(|segment| {
+                segment.end > HEADER_LEN && segment.end + record_len(len) > SEGMENT_LEN
+            })
+        };
+        if full(self) && self.dead * 2 > self.segments.iter().map(|segment| segment.end).sum() {
+            self.compact(table_path)?;
+        }
+        if full(self) {
+            self.segments.push(Segment {
+                number: self.segments.last().map_or(0, |segment| segment.number + 1),
+                len: 0,
+                modified: None,
+                end: 0,
+            });
+        }
+        let i = self.segments.len() - 1;
+        let segment = &self.segments[i];
+        let mut record = Vec::with_capacity(HEADER_LEN as usize + record_len(len) as usize);
+        if segment.end == 0 {
+            // new segment
+            record.extend_from_slice(MAGIC);
+            record.push(SEGMENT_VERSION);
+        }
+        let offset = segment.end + record.len() as u64;
+        let framed = record.len();
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(&(pos as u64).to_le_bytes());
+        record.push(kind);
+        record.extend_from_slice(row);
+        let checksum = format::checksum(&record[framed..]);
+        record.extend_from_slice(&checksum.to_le_bytes());
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(segment_path(table_path, segment.number))?;
+        if segment.len != segment.end {
+            file.set_len(segment.end)?; // cut off an unfinished record
+        }
+        file.seek(SeekFrom::Start(segment.end))?;
+        file.write_all(&record)?;
+        file.sync_data()?; // before the index file or the info file refer to the row
+        let modified = file.metadata()?.modified().ok();
+        let segment = &mut self.segments[i];
+        segment.end += record.len() as u64;
+        segment.len = segment.end;
+        segment.modified = modified;
+        let location = Location {
+            segment: segment.number,
+            offset,
+            len,
+            checksum,
+        };
+        self.apply(pos, kind, location);
+        Ok(checksum)
+    }
+    fn read(&self, table_path: &str, pos: usize, location: Location) -> Result<Vec<u8>, JadbError> {
+        let path = segment_path(table_path, location.segment);
+        let mut file = std::fs::File::open(&path)?;
+        file.seek(SeekFrom::Start(location.offset))?;
+        let mut record = vec![0u8; record_len(location.len) as usize];
+        file.read_exact(&mut record)?;
+        check(&record, pos, location, &path)?;
+        Ok(record[FRAME_LEN as usize..record.len() - CHECKSUM_LEN as usize].to_vec())
+    }
+    fn compact(&mut self, table_path: &str) -> Result<(), JadbError> {
+        // new segments are numbered after the old ones, so if this is interrupted, their records are still the last ones
+        let mut by_segment: BTreeMap<u32, Vec<(usize, Location)>> = BTreeMap::new();
+        for (&pos, &location) in &self.rows {
+            by_segment
+                .entry(location.segment)
+                .or_default()
+                .push((pos, location));
+        }
+        let mut number = self.segments.last().map_or(0, |segment| segment.number + 1);
+        let mut rows = BTreeMap::new();
+        let mut written = vec![];
+        let mut content: Vec<u8> = vec![];
+        for (segment, mut locations) in by_segment {
+            locations.sort_by_key(|(_, location)| location.offset); // keep the order of the records
+            let path = segment_path(table_path, segment);
+            let old = std::fs::read(&path)?;
+            for (pos, location) in locations {
+                let start = location.offset as usize;
+                let record = old
+                    .get(start..start + record_len(location.len) as usize)
+                    .ok_or_else(|| JadbError::Corrupt(format!("row {} in {}", pos, path)))?;
+                check(record, pos, location, &path)?;
+                if content.len() as u64 + record.len() as u64 > SEGMENT_LEN && !content.is_empty() {
+                    written.push(write_segment(table_path, number, &content)?);
+                    content.clear();
+                    number += 1;
+                }
+                if content.is_empty() {
+                    content.extend_from_slice(MAGIC);
+                    content.push(SEGMENT_VERSION);
+                }
+                rows.insert(
+                    pos,
+                    Location {
+                        segment: number,
+                        offset: content.len() as u64,
+                        ..location
+                    },
+                );
+                content.extend_from_slice(record);
+            }
+        }
+        if !content.is_empty() {
+            written.push(write_segment(table_path, number, &content)?);
+        }
+        // the new segments were synced when they were written, the renames are synced before the old segments are removed
+        let mut segments = Vec::with_capacity(written.len());
+        for (number, len) in written {
+            let path = segment_path(table_path, number);
+            std::fs::rename(format!("{}.tmp", path), &path)?;
+            segments.push(Segment {
+                number,
+                len,
+                modified: std::fs::metadata(&path)?.modified().ok(),
+                end: len,
+            });
+        }
+        crate::sync_dir(table_path)?;
+        for segment in &self.segments {
+            match std::fs::remove_file(segment_path(table_path, segment.number)) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(JadbError::Io(err))
+                }
+                _ => {}
+            }
+        }
+        *self = Store {
+            segments,
+            rows,
+            dead: 0,
+        };
+        Ok(())
+    }
+}
+
+fn write_segment(table_path: &str, number: u32, content: &[u8]) -> Result<(u32, u64), JadbError> {
+    // written to a temporary file, which is renamed when all new segments are written
+    let mut file = std::fs::File::create(format!("{}.tmp", segment_path(table_path, number)))?;
+    file.write_all(content)?;
+    file.sync_all()?; // on disk before it replaces the old segments
+    Ok((number, content.len() as u64))
+}
+
+fn check(record: &[u8], pos: usize, location: Location, path: &str) -> Result<(), JadbError> {
+    // the record must be the one of the row and unchanged
+    let (framed, checksum) = record.split_at(record.len() - CHECKSUM_LEN as usize);
+    if le_u64(&framed[4..12]) != pos as u64
+        || framed[12] != WRITTEN
+        || le_u64(checksum) != location.checksum
+        || format::checksum(framed) != location.checksum
+    {
+        return Err(JadbError::Corrupt(format!("row {} in {}", pos, path)));
+    }
+    Ok(())
+}
+
+fn old_rows(table_path: &str) -> Result<Vec<usize>, JadbError> {
+    // positions of the row files of older versions
+    let mut rows = vec![];
+    for entry in std::fs::read_dir(table_path)? {
+        let file_name = entry?.file_name();
+        let row_num = file_name.to_string_lossy();
+        if row_num == "info.jadb"
+            || row_num == LOCK_NAME
+            || row_num == index_file::INDEX_NAME
+            || row_num.ends_with(".tmp")
+            || segment_number(&row_num).is_some()
+        {
+            continue; // not a row file, or a file which was never finished
+        }
+        rows.push(row_num.parse::<usize>().map_err(|_| {
+            JadbError::Corrupt(format!("{} isn't a row of {}", row_num, table_path))
+        })?);
+    }
+    Ok(rows)
+}
+
+fn remove_old_row(table_path: &str, pos: usize) -> Result<bool, JadbError> {
+    match std::fs::remove_file(old_row_path(table_path, pos)) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(JadbError::Io(err)),
+    }
+}
+
+fn old_row_path(table_path: &str, pos: usize) -> String {
+    format!("{}/{}", table_path, pos)
+}
+
+fn segment_path(table_path: &str, number: u32) -> String {
+    format!("{}/segment-{}.jadb", table_path, number)
+}
+
+fn segment_number(file_name: &str) -> Option<u32> {
+    file_name
+        .strip_prefix("segment-")?
+        .strip_suffix(".jadb")?
+        .parse()
+        .ok()
+}
+
+fn record_len(len: u32) -> u64 {
+    FRAME_LEN + len as u64 + CHECKSUM_LEN
+}
+
+fn le_u64(bytes: &[u8]) -> u64 {
+    let mut number = [0u8; 8];
+    number.copy_from_slice(bytes);
+    u64::from_le_bytes(number)
+}
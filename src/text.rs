@@ -0,0 +1,204 @@
+// the full-text index of a table
+//
+// Fields are split into words at Unicode word boundaries and lowercased. Optionally, common English words are skipped
+// and words are reduced to their stem by stripping plural and verb suffixes. Every field is a document of the index,
+// ranked with BM25 against the other fields of its table.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{Hit, JadbError};
+
+// BM25 parameters: saturation of the term frequency and strength of the length normalization
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+// skipped if stopwords are enabled, sorted for binary search
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it",
+    "no", "not", "of", "on", "or", "such", "that", "the", "their", "then", "there", "these",
+    "they", "this", "to", "was", "will", "with",
+];
+
+// suffixes stripped by the stemmer and their replacements, the first matching one is used
+const SUFFIXES: &[(&str, &str)] = &[
+    ("sses", "ss"),
+    ("ies", "y"),
+    ("ches", "ch"),
+    ("shes", "sh"),
+    ("xes", "x"),
+    ("ss", "ss"),
+    ("us", "us"),
+    ("ing", ""),
+    ("ed", ""),
+    ("s", ""),
+];
+const MIN_STEM_LEN: usize = 3;
+
+/// # FullText
+///
+/// The options of the full-text index of a table. Fields are always split into words at Unicode word boundaries and lowercased.
+///
+/// ## Examples
+/// ```
+/// let full_text = jadb::FullText {
+///     stemming: true,
+///     stopwords: true,
+/// };
+///
+/// assert_eq!(full_text.tokens("The cats are sleeping"), vec!["cat", "sleep"]);
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FullText {
+    /// Reduce English words to their stem, so e.g. `cats` is found by `cat`.
+    pub stemming: bool,
+    /// Skip common English words like `the` and `is`.
+    pub stopwords: bool,
+}
+
+/// # TextHit
+///
+/// A field found by a full-text search, with its BM25 score and the byte ranges of the words matching the query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextHit {
+    pub hit: Hit,
+    pub score: f64,
+    pub highlights: Vec<Range<usize>>,
+}
+
+impl FullText {
+    /// Returns the terms of the text, as they are saved in the index.
+    pub fn tokens(&self, text: &str) -> Vec<String> {
+        self.token_ranges(text)
+            .into_iter()
+            .map(|(_, term)| term)
+            .collect()
+    }
+    /// Returns the terms of the text with their byte ranges in it.
+    pub(crate) fn token_ranges(&self, text: &str) -> Vec<(Range<usize>, String)> {
+        text.split_word_bound_indices()
+            .filter(|(_, word)| word.chars().any(char::is_alphanumeric))
+            .map(|(start, word)| (start..start + word.len(), word.to_lowercase()))
+            .filter(|(_, word)| !self.stopwords || STOPWORDS.binary_search(&word.as_str()).is_err())
+            .map(|(range, word)| match self.stemming {
+                true => (range, stem(&word)),
+                false => (range, word),
+            })
+            .collect()
+    }
+    /// Value of the `full text` line in the info file.
+    pub(crate) fn info(&self) -> String {
+        let mut options = vec!["words"];
+        if self.stemming {
+            options.push("stemming");
+        }
+        if self.stopwords {
+            options.push("stopwords");
+        }
+        options.join(", ")
+    }
+    pub(crate) fn from_info(info: &str) -> Result<FullText, JadbError> {
+        let mut full_text = FullText::default();
+        for option in info.split(", ") {
+            match option {
+                "words" => {}
+                "stemming" => full_text.stemming = true,
+                "stopwords" => full_text.stopwords = true,
+                _ => return Err(JadbError::UnsupportedFormat(format!("full text {}", info))),
+            }
+        }
+        Ok(full_text)
+    }
+}
+
+/// The full-text index of one table: the fields containing each term and the number of terms of each field.
+#[derive(Clone, Debug)]
+pub(crate) struct TextIndex {
+    pub(crate) options: FullText,
+    postings: HashMap<String, BTreeMap<(usize, usize), u32>>, // frequency of each term by row and field position
+    lengths: BTreeMap<(usize, usize), u32>, // number of terms by row and field position
+    total_len: u64,
+}
+
+impl TextIndex {
+    pub(crate) fn new(options: FullText) -> TextIndex {
+        TextIndex {
+            options,
+            postings: HashMap::new(),
+            lengths: BTreeMap::new(),
+            total_len: 0,
+        }
+    }
+    pub(crate) fn add_field(&mut self, row: usize, field: usize, text: &str) {
+        let terms = self.options.tokens(text);
+        if terms.is_empty() {
+            return; // nothing to find
+        }
+        for term in &terms {
+            *self
+                .postings
+                .entry(term.clone())
+                .or_default()
+                .entry((row, field))
+                .or_default() += 1;
+        }
+        self.lengths.insert((row, field), terms.len() as u32);
+        self.total_len += terms.len() as u64;
+    }
+    pub(crate) fn remove_field(&mut self, row: usize, field: usize, text: &str) {
+        let len = match self.lengths.remove(&(row, field)) {
+            Some(len) => len,
+            None => return,
+        };
+        self.total_len -= len as u64;
+        for term in self.options.tokens(text) {
+            if let Some(fields) = self.postings.get_mut(&term) {
+                fields.remove(&(row, field));
+                if fields.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+    }
+    /// Returns the BM25 score of every field containing any of the terms.
+    pub(crate) fn scores(&self, terms: &[String]) -> BTreeMap<(usize, usize), f64> {
+        let mut scores = BTreeMap::new();
+        if self.lengths.is_empty() {
+            return scores;
+        }
+        let count = self.lengths.len() as f64;
+        let avg_len = self.total_len as f64 / count;
+        for term in terms {
+            let fields = match self.postings.get(term) {
+                Some(fields) => fields,
+                None => continue,
+            };
+            let found_in = fields.len() as f64;
+            let idf = ((count - found_in + 0.5) / (found_in + 0.5) + 1.0).ln();
+            for (&key, &freq) in fields {
+                let freq = freq as f64;
+                let len = self.lengths[&key] as f64;
+                *scores.entry(key).or_insert(0.0) +=
+                    idf * freq * (K1 + 1.0) / (freq + K1 * (1.0 - B + B * len / avg_len));
+            }
+        }
+        scores
+    }
+}
+
+fn stem(word: &str) -> String {
+    // light English stemmer, keeps at least a few characters of every word
+    match SUFFIXES
+        .iter()
+        .find_map(|(suffix, replacement)| Some((word.strip_suffix(suffix)?, replacement)))
+    {
+        Some((stem, replacement))
+            if stem.chars().count() + replacement.chars().count() >= MIN_STEM_LEN =>
+        {
+            format!("{}{}", stem, replacement)
+        }
+        _ => word.to_string(), // too short to strip the suffix
+    }
+}
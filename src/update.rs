@@ -0,0 +1,166 @@
+// updates of the fields of an existing row
+//
+// The updates are applied in order with a cursor over the old fields: every update but `Insert` uses the old field at the cursor
+// and moves it to the next one. `Insert` adds a field before the cursor and `Remove` skips the old field without adding one.
+
+use crate::{JadbError, Value};
+
+/// # Update
+///
+/// How a field of a row is changed by [`Table::update()`](crate::Table::update()), or by the operators of [`Table::update_fields()`](crate::Table::update_fields()).
+///
+/// ## Examples
+/// ```
+/// use jadb::{Update, Value};
+///
+/// let updates = vec![Update::Keep, Update::Increment(Value::Int(1)), Update::Append(Value::from("!"))];
+///
+/// assert_eq!(Update::parse("|+1")?, updates[1]);
+/// # Ok::<(), jadb::JadbError>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Update {
+    /// Keep the old field. Written as `|o`.
+    Keep,
+    /// Replace the old field with the value. Every field without an operator sets its text.
+    Set(Value),
+    /// Add the text or bytes to the end of the old field. Written as `|a` followed by the text.
+    Append(Value),
+    /// Add the number to the old field. Written as `|+` followed by the number.
+    Increment(Value),
+    /// Subtract the number from the old field. Written as `|-` followed by the number.
+    Decrement(Value),
+    /// Replace the old field with null. Written as `|n`.
+    Null,
+    /// Replace the old field with the default of its column, or null in tables without a schema. Written as `|d`.
+    Default,
+    /// Add a new field before the old field, which is kept for the next update. Written as `|i` followed by the text.
+    Insert(Value),
+    /// Remove the old field. Written as `|r`.
+    Remove,
+}
+
+impl Update {
+    /// Returns the update written as a field of [`Table::update_fields()`](crate::Table::update_fields()).
+    ///
+    /// A field starting with `||` sets the text after the first `|`, so e.g. `||o` sets `|o`.
+    /// Every other field without an operator sets its text.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `JadbError::InvalidUpdate` if the text after `|+` or `|-` isn't a number.
+    pub fn parse(field: &str) -> Result<Update, JadbError> {
+        let parse_number = |text: &str| {
+            number(text)
+                .ok_or_else(|| JadbError::InvalidUpdate(format!("{:?} isn't a number", text)))
+        };
+        Ok(match field {
+            "|o" => Update::Keep,
+            "|n" => Update::Null,
+            "|d" => Update::Default,
+            "|r" => Update::Remove,
+            _ => match field.get(..2).unwrap_or_default() {
+                "|a" => Update::Append(Value::from(&field[2..])),
+                "|+" => Update::Increment(parse_number(&field[2..])?),
+                "|-" => Update::Decrement(parse_number(&field[2..])?),
+                "|i" => Update::Insert(Value::from(&field[2..])),
+                "||" => Update::Set(Value::from(&field[1..])),
+                _ => Update::Set(Value::from(field)),
+            },
+        })
+    }
+}
+
+/// Returns the new fields of a row from its old ones, `None` for fields which get their default. If `keep_rest` is set,
+/// the old fields after the last update are kept, otherwise they are dropped.
+pub(crate) fn apply(
+    old: &[Value],
+    updates: Vec<Update>,
+    keep_rest: bool,
+) -> Result<Vec<Option<Value>>, JadbError> {
+    let mut new = Vec::with_capacity(updates.len());
+    let mut cursor = 0;
+    for update in updates {
+        let old_field = || {
+            old.get(cursor).ok_or(JadbError::IndexOutOfRange {
+                index: cursor,
+                len: old.len(),
+            })
+        };
+        let value = match update {
+            Update::Insert(value) => {
+                new.push(Some(value)); // the cursor stays at the old field
+                continue;
+            }
+            Update::Remove => {
+                old_field()?; // must exist
+                cursor += 1;
+                continue;
+            }
+            Update::Keep => Some(old_field()?.clone()),
+            Update::Set(value) => Some(value),
+            Update::Append(value) => Some(append(old_field()?, value)?),
+            Update::Increment(number) => Some(add(old_field()?, number, false)?),
+            Update::Decrement(number) => Some(add(old_field()?, number, true)?),
+            Update::Null => Some(Value::Null),
+            Update::Default => None,
+        };
+        new.push(value);
+        cursor += 1;
+    }
+    if keep_rest {
+        new.extend(old.iter().skip(cursor).cloned().map(Some));
+    }
+    Ok(new)
+}
+
+fn append(old: &Value, value: Value) -> Result<Value, JadbError> {
+    match (old, value) {
+        (Value::Text(old), Value::Text(text)) => Ok(Value::Text(format!("{}{}", old, text))),
+        (Value::Bytes(old), Value::Bytes(bytes)) => Ok(Value::Bytes([&old[..], &bytes].concat())),
+        (old, value) => Err(JadbError::InvalidUpdate(format!(
+            "can't append {:?} to {:?}",
+            value, old
+        ))),
+    }
+}
+
+fn add(old: &Value, amount: Value, subtract: bool) -> Result<Value, JadbError> {
+    let invalid = || JadbError::InvalidUpdate(format!("can't add {:?} to {:?}", amount, old));
+    let (old_number, as_text) = match old {
+        Value::Text(text) => (number(text), true), // numbers written as text stay text
+        Value::Int(_) | Value::Float(_) => (Some(old.clone()), false),
+        _ => (None, false),
+    };
+    let old_number = old_number.ok_or_else(invalid)?;
+    let sum = match (&old_number, &amount, subtract) {
+        (Value::Int(a), Value::Int(b), false) => a.checked_add(*b).map(Value::Int),
+        (Value::Int(a), Value::Int(b), true) => a.checked_sub(*b).map(Value::Int),
+        (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_), _) => {
+            let (a, b) = (float(&old_number), float(&amount));
+            Some(Value::Float(if subtract { a - b } else { a + b }))
+        }
+        _ => None,
+    }
+    .ok_or_else(invalid)?;
+    Ok(match as_text {
+        true => Value::Text(sum.to_string()),
+        false => sum,
+    })
+}
+
+fn number(text: &str) -> Option<Value> {
+    match (text.parse(), text.parse()) {
+        (Ok(int), _) => Some(Value::Int(int)),
+        (_, Ok(float)) => Some(Value::Float(float)),
+        _ => None,
+    }
+}
+
+fn float(number: &Value) -> f64 {
+    match number {
+        Value::Int(int) => *int as f64,
+        Value::Float(float) => *float,
+        _ => f64::NAN,
+    }
+}
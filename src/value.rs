@@ -0,0 +1,322 @@
+// typed field values and their binary encoding
+//
+// Since format version 5, the plaintext of a row is the sequence of its values, each a type tag followed by its content:
+//
+// | Tag | Type | Content |
+// | --- | ---- | ------- |
+// | 0 | Null | nothing |
+// | 1 | Bool | 1 byte, 0 or 1 |
+// | 2 | Int | i64, little endian |
+// | 3 | Float | f64 bits, little endian |
+// | 4 | Text | length (u32, little endian) and UTF-8 bytes |
+// | 5 | Bytes | length (u32, little endian) and bytes |
+// | 6 | Timestamp | seconds since the Unix epoch (i64) and nanoseconds (u32), little endian |
+//
+// Rows of older format versions contain text fields separated by `\n`.
+
+use std::cmp::Ordering;
+
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+
+use crate::JadbError;
+
+const NULL: u8 = 0;
+const BOOL: u8 = 1;
+const INT: u8 = 2;
+const FLOAT: u8 = 3;
+const TEXT: u8 = 4;
+const BYTES: u8 = 5;
+const TIMESTAMP: u8 = 6;
+
+/// # Value
+///
+/// The typed content of a field.
+///
+/// Values are ordered by type first: null, booleans, numbers, texts, bytes and timestamps. Integers and floats are compared by their exact numeric value,
+/// so an integer and a float of the same value are equal. Floats are ordered by [`f64::total_cmp()`], so every `NaN` equals itself, except that `-0.0` equals `0.0`.
+///
+/// Values are displayed like they are read with [`Table::read()`](crate::Table::read()) and found by searches: null as the empty string,
+/// bytes in lowercase hex and timestamps in RFC 3339.
+///
+/// ## Examples
+/// ```
+/// use jadb::Value;
+///
+/// assert!(Value::Int(2) < Value::Float(2.5));
+/// assert!(Value::Float(f64::MAX) < Value::Text(String::new()));
+/// assert_eq!(Value::Bytes(vec![0xca, 0xfe]).to_string(), "cafe");
+/// ```
+#[derive(Clone, Debug)]
+pub enum Value {
+    /// No value.
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// A 64 bit signed integer.
+    Int(i64),
+    /// A 64 bit float, saved bit for bit.
+    Float(f64),
+    /// UTF-8 text, which may contain any character including `\n`.
+    Text(String),
+    /// Binary data.
+    Bytes(Vec<u8>),
+    /// A point in time with nanosecond precision.
+    Timestamp(DateTime<Utc>),
+}
+
+impl Value {
+    fn rank(&self) -> u8 {
+        // position of the type in the order of values, integers and floats share one
+        match self {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Int(_) | Value::Float(_) => 2,
+            Value::Text(_) => 3,
+            Value::Bytes(_) => 4,
+            Value::Timestamp(_) => 5,
+        }
+    }
+    /// Whether the values are of the same type, integers and floats are both numbers.
+    pub(crate) fn same_kind(&self, other: &Value) -> bool {
+        self.rank() == other.rank()
+    }
+    /// Returns the smallest value of the same type.
+    pub(crate) fn first_of_kind(&self) -> Value {
+        match self {
+            Value::Null => Value::Null,
+            Value::Bool(_) => Value::Bool(false),
+            Value::Int(_) | Value::Float(_) => Value::Float(f64::from_bits(u64::MAX)), // negative NaN, before -inf
+            Value::Text(_) => Value::Text(String::new()),
+            Value::Bytes(_) => Value::Bytes(vec![]),
+            Value::Timestamp(_) => Value::Timestamp(DateTime::<Utc>::MIN_UTC),
+        }
+    }
+    /// Appends the binary encoding of the value. Returns `JadbError::TooLong` if a text or bytes are longer than their length can be saved.
+    pub(crate) fn encode(&self, out: &mut Vec<u8>) -> Result<(), JadbError> {
+        match self {
+            Value::Null => out.push(NULL),
+            Value::Bool(value) => out.extend_from_slice(&[BOOL, u8::from(*value)]),
+            Value::Int(value) => {
+                out.push(INT);
+                out.extend_from_slice(&value.to_le_bytes());
+            }
+            Value::Float(value) => {
+                out.push(FLOAT);
+                out.extend_from_slice(&value.to_bits().to_le_bytes());
+            }
+            Value::Text(value) => {
+                out.push(TEXT);
+                out.extend_from_slice(&encode_len(value.len())?);
+                out.extend_from_slice(value.as_bytes());
+            }
+            Value::Bytes(value) => {
+                out.push(BYTES);
+                out.extend_from_slice(&encode_len(value.len())?);
+                out.extend_from_slice(value);
+            }
+            Value::Timestamp(value) => {
+                out.push(TIMESTAMP);
+                out.extend_from_slice(&value.timestamp().to_le_bytes());
+                out.extend_from_slice(&value.timestamp_subsec_nanos().to_le_bytes());
+            }
+        }
+        Ok(())
+    }
+    /// Reads one value from the start of the input and advances it, `None` if the input doesn't start with a valid value.
+    pub(crate) fn decode(input: &mut &[u8]) -> Option<Value> {
+        let (&tag, rest) = input.split_first()?;
+        *input = rest;
+        Some(match tag {
+            NULL => Value::Null,
+            BOOL => match take(input, 1)? {
+                [0] => Value::Bool(false),
+                [1] => Value::Bool(true),
+                _ => return None,
+            },
+            INT => Value::Int(i64::from_le_bytes(take(input, 8)?.try_into().ok()?)),
+            FLOAT => Value::Float(f64::from_bits(u64::from_le_bytes(
+                take(input, 8)?.try_into().ok()?,
+            ))),
+            TEXT => {
+                let len = u32::from_le_bytes(take(input, 4)?.try_into().ok()?) as usize;
+                Value::Text(String::from_utf8(take(input, len)?.to_vec()).ok()?)
+            }
+            BYTES => {
+                let len = u32::from_le_bytes(take(input, 4)?.try_into().ok()?) as usize;
+                Value::Bytes(take(input, len)?.to_vec())
+            }
+            TIMESTAMP => {
+                let secs = i64::from_le_bytes(take(input, 8)?.try_into().ok()?);
+                let nanos = u32::from_le_bytes(take(input, 4)?.try_into().ok()?);
+                Value::Timestamp(Utc.timestamp_opt(secs, nanos).single()?)
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// Returns the binary encoding of the values of a row.
+pub(crate) fn encode_row(values: &[Value]) -> Result<Vec<u8>, JadbError> {
+    let mut out = vec![];
+    for value in values {
+        value.encode(&mut out)?;
+    }
+    Ok(out)
+}
+
+fn encode_len(len: usize) -> Result<[u8; 4], JadbError> {
+    // lengths of texts and bytes are saved as u32
+    u32::try_from(len)
+        .map(u32::to_le_bytes)
+        .map_err(|_| JadbError::TooLong {
+            len,
+            max: u32::MAX as usize,
+        })
+}
+
+/// Returns the values of a row from their binary encoding.
+pub(crate) fn decode_row(mut input: &[u8]) -> Result<Vec<Value>, JadbError> {
+    let mut values = vec![];
+    while !input.is_empty() {
+        values.push(
+            Value::decode(&mut input)
+                .ok_or_else(|| JadbError::Corrupt(String::from("invalid value in row")))?,
+        );
+    }
+    Ok(values)
+}
+
+fn take<'a>(input: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if input.len() < len {
+        return None;
+    }
+    let (taken, rest) = input.split_at(len);
+    *input = rest;
+    Some(taken)
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Text(value) => write!(f, "{}", value),
+            Value::Bytes(value) => {
+                for byte in value {
+                    write!(f, "{:02x}", byte)?;
+                }
+                Ok(())
+            }
+            Value::Timestamp(value) => {
+                write!(f, "{}", value.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+            }
+        }
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            (Value::Int(a), Value::Int(b)) => a.cmp(b),
+            (Value::Float(a), Value::Float(b)) => positive_zero(*a).total_cmp(&positive_zero(*b)),
+            (Value::Int(a), Value::Float(b)) => cmp_int_float(*a, *b),
+            (Value::Float(a), Value::Int(b)) => cmp_int_float(*b, *a).reverse(),
+            (Value::Text(a), Value::Text(b)) => a.cmp(b),
+            (Value::Bytes(a), Value::Bytes(b)) => a.cmp(b),
+            (Value::Timestamp(a), Value::Timestamp(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()), // nulls and values of different types
+        }
+    }
+}
+
+fn positive_zero(float: f64) -> f64 {
+    // -0.0 equals 0.0 like it equals the integer 0, total_cmp alone would put it before 0.0
+    if float == 0.0 {
+        0.0
+    } else {
+        float
+    }
+}
+
+fn cmp_int_float(int: i64, float: f64) -> Ordering {
+    // exact, converting either to the other's type would round
+    if float.is_nan() {
+        return 0f64.total_cmp(&float); // like total_cmp, NaNs are outside of all numbers
+    }
+    if float >= 9_223_372_036_854_775_808.0 {
+        return Ordering::Less; // 2^63, larger than every i64
+    }
+    if float < -9_223_372_036_854_775_808.0 {
+        return Ordering::Greater;
+    }
+    let whole = float.trunc();
+    int.cmp(&(whole as i64)).then_with(|| {
+        0f64.partial_cmp(&(float - whole))
+            .unwrap_or(Ordering::Equal)
+    })
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Value {}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Int(value.into())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(value: Vec<u8>) -> Self {
+        Value::Bytes(value)
+    }
+}
+
+impl From<DateTime<Utc>> for Value {
+    fn from(value: DateTime<Utc>) -> Self {
+        Value::Timestamp(value)
+    }
+}
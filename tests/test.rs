@@ -1,166 +1,320 @@
 #[cfg(test)]
 mod tests {
-    use jadb;
     use std::fs;
     use std::path::Path;
 
     use aes_gcm::aead::NewAead;
-    use aes_gcm::{Aes128Gcm, Key};
+    use aes_gcm::{Aes128Gcm, Aes256Gcm, Key};
+    use chacha20poly1305::XChaCha20Poly1305;
 
-    fn a_delete(test_table: jadb::Table, info_path: String) {
+    use jadb::HashStorage;
+
+    fn a_delete(path: &str) {
         // prefix with letter so tests are run as intended in alphabetical order
-        if Path::new(&info_path).exists() {
-            fs::remove_dir_all(test_table.path).expect("Couldn't delete test files.");
+        if Path::new(path).exists() {
+            fs::remove_dir_all(path).expect("Couldn't delete test files.");
+        }
+        assert!(!Path::new(path).exists());
+    }
+    fn a_setup(path: &str, rows: &[&str]) -> (HashStorage, Aes128Gcm) {
+        // every test gets its own table, so they can run in parallel
+        a_delete(path);
+        let test_table = jadb::Table { path, id: 0 };
+        let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+        let mut hasher = HashStorage::new();
+        test_table.create().expect("Couldn't create test table");
+        jadb::init(test_table, &mut hasher, &cipher).expect("Couldn't init test table");
+        for (pos, content) in rows.iter().enumerate() {
+            test_table
+                .write(content, jadb::Row { pos }, &mut hasher, &cipher)
+                .expect("Couldn't write test row");
+        }
+        (hasher, cipher)
+    }
+    fn a_records(path: &str) -> Vec<(usize, Vec<u8>)> {
+        // every record in the segment files of the table with its row position, in the order they were written
+        let mut numbers: Vec<u32> = fs::read_dir(path)
+            .unwrap()
+            .filter_map(|entry| {
+                let name = entry.unwrap().file_name().into_string().unwrap();
+                name.strip_prefix("segment-")?
+                    .strip_suffix(".jadb")?
+                    .parse()
+                    .ok()
+            })
+            .collect();
+        numbers.sort_unstable();
+        let mut records = vec![];
+        for number in numbers {
+            let segment = fs::read(format!("{}/segment-{}.jadb", path, number)).unwrap();
+            assert_eq!(&segment[..5], b"JADS\x01"); // magic and segment version
+            let mut at = 5;
+            while at < segment.len() {
+                let len = u32::from_le_bytes(segment[at..at + 4].try_into().unwrap()) as usize;
+                let pos = u64::from_le_bytes(segment[at + 4..at + 12].try_into().unwrap());
+                records.push((pos as usize, segment[at..at + 13 + len + 8].to_vec()));
+                at += 13 + len + 8;
+            }
         }
-        assert_eq!(Path::new(&info_path).exists(), false);
+        records
+    }
+    fn a_row(record: &[u8]) -> &[u8] {
+        // the row in a record, between length, position and kind and the checksum
+        &record[13..record.len() - 8]
     }
     #[test]
     fn a_test_create() {
         let test_table = jadb::Table {
-            path: "tests/test_dir/test_db",
+            path: "tests/test_dir/test_create",
             id: 0,
         };
         let info_path = format!("{}/{}", test_table.path, "info.jadb");
-        a_delete(test_table, info_path.clone());
+        a_delete(test_table.path);
         let c_res = test_table.create();
-        assert_eq!(c_res, 0);
-        assert_eq!(Path::new(&info_path).exists(), true);
-        assert_eq!(test_table.create(), 1);
+        assert!(c_res.is_ok());
+        assert!(Path::new(&info_path).exists());
+        assert!(matches!(
+            test_table.create(),
+            Err(jadb::JadbError::AlreadyExists(_))
+        ));
 
         let test_table_2 = jadb::Table {
-            path: "tests/test_dir/test_db",
+            path: "tests/test_dir/test_create",
             id: 1,
         };
-        assert_eq!(test_table_2.create(), 1);
+        assert!(matches!(
+            test_table_2.create(),
+            Err(jadb::JadbError::AlreadyExists(_))
+        ));
 
         let test_table_3 = jadb::Table { path: "", id: 2 };
-        assert_eq!(test_table_3.create(), 1);
+        assert!(matches!(
+            test_table_3.create(),
+            Err(jadb::JadbError::EmptyPath)
+        ));
     }
     #[test]
     fn b_test_write() {
         let test_table = jadb::Table {
-            path: "tests/test_dir/test_db",
+            path: "tests/test_dir/test_write",
             id: 0,
         };
         let test_row = jadb::Row { pos: 0 };
-        let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
-        let mut hasher: Vec<Vec<std::collections::HashMap<String, usize>>> =
-            vec![vec![std::collections::HashMap::new()]];
-        jadb::init(test_table, &mut hasher, &cipher);
+        let (mut hasher, cipher) = a_setup(test_table.path, &[]);
         let w_res = test_table.write("hi", test_row, &mut hasher, &cipher);
-        assert_eq!(w_res, 0);
-        assert_eq!(
-            fs::read(format!("{}/{}", test_table.path, 0)).expect("Couldn't read test"),
-            vec![63, 47, 135, 212, 103, 39, 146, 86, 145, 189, 43, 116, 98, 114, 112, 53, 101, 179]
-        );
-        assert_eq!(test_table.write("", test_row, &mut hasher, &cipher), 1);
+        assert!(w_res.is_ok());
+        let (pos, first) = a_records(test_table.path).pop().unwrap();
+        let first = a_row(&first).to_vec();
+        assert_eq!(pos, 0);
+        assert_eq!(first.len(), 4 + 1 + 8 + 8 + 4 + 1 + 12 + 1 + 4 + 2 + 16); // header, encoded text and tag
+        assert_eq!(&first[..5], b"JADB\x06"); // magic and format version
+        test_table
+            .write("hi", test_row, &mut hasher, &cipher)
+            .unwrap();
+        let (_, second) = a_records(test_table.path).pop().unwrap();
+        assert_ne!(first[26..38], a_row(&second)[26..38]); // every write gets a new nonce
+        assert!(matches!(
+            test_table.write("", test_row, &mut hasher, &cipher),
+            Err(jadb::JadbError::EmptyContent)
+        ));
+        assert!(matches!(
+            test_table.write("|o\n|o", test_row, &mut hasher, &cipher),
+            Err(jadb::JadbError::IndexOutOfRange { index: 1, len: 1 })
+        ));
         for i in 1..20 {
-            assert_eq!(
-                test_table.write("hi", jadb::Row { pos: i }, &mut hasher, &cipher),
-                0
-            );
+            assert!(test_table
+                .write("hi", jadb::Row { pos: i }, &mut hasher, &cipher)
+                .is_ok());
         }
+        assert_eq!(fs::read_dir(test_table.path).unwrap().count(), 4); // info, index, lock and one segment file
     }
     #[test]
     fn c_test_read() {
         let test_table = jadb::Table {
-            path: "tests/test_dir/test_db",
+            path: "tests/test_dir/test_read",
             id: 0,
         };
         let test_row = jadb::Row { pos: 0 };
-        let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
-        let con = test_table.read(test_row, &cipher);
+        let (_, cipher) = a_setup(test_table.path, &["hi"]);
+        let con = test_table
+            .read(test_row, &cipher)
+            .expect("Couldn't read row");
         let v_con = vec![String::from("hi")];
         assert_eq!(con, v_con);
+
+        assert!(matches!(
+            test_table.read(jadb::Row { pos: 1 }, &cipher),
+            Err(jadb::JadbError::NotFound(_))
+        ));
+        let wrong_cipher = Aes128Gcm::new(Key::from_slice(b"0000000000000000"));
+        assert!(matches!(
+            test_table.read(test_row, &wrong_cipher),
+            Err(jadb::JadbError::UnknownKey { .. })
+        ));
     }
     #[test]
     fn d_test_len() {
         let test_table = jadb::Table {
-            path: "tests/test_dir/test_db",
+            path: "tests/test_dir/test_len",
             id: 0,
         };
         let test_row = jadb::Row { pos: 0 };
         let test_field = jadb::Field { pos: 0 };
-        let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+        let (_, cipher) = a_setup(test_table.path, &["hi"]);
         assert_eq!(
-            test_row.length(test_table, jadb::LenType::Characters, &cipher),
+            test_row
+                .length(test_table, jadb::LenType::Characters, &cipher)
+                .unwrap(),
             2
         );
         assert_eq!(
-            test_row.length(test_table, jadb::LenType::Fields, &cipher),
+            test_row
+                .length(test_table, jadb::LenType::Fields, &cipher)
+                .unwrap(),
             1
         );
-        assert_eq!(test_field.length(test_table, test_row, &cipher), 2);
+        assert_eq!(test_field.length(test_table, test_row, &cipher).unwrap(), 2);
+        assert!(matches!(
+            jadb::Field { pos: 1 }.length(test_table, test_row, &cipher),
+            Err(jadb::JadbError::IndexOutOfRange { index: 1, len: 1 })
+        ));
     }
     #[test]
     fn e_test_hash() {
         let test_table = jadb::Table {
-            path: "tests/test_dir/test_db",
+            path: "tests/test_dir/test_hash",
             id: 0,
         };
         let test_row = jadb::Row { pos: 0 };
         let test_field = jadb::Field { pos: 0 };
-        let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+        let (_, cipher) = a_setup(test_table.path, &["hi"]);
+        assert_eq!(
+            test_row.shash_debug(test_table, "hi", &cipher).unwrap(),
+            9054111691838703432
+        );
         assert_eq!(
-            test_row.shash_debug(test_table, "hi", &cipher),
-            17259954866336786813
+            test_row.shash(test_table, &cipher).unwrap(),
+            9054111691838703432
         );
-        assert_eq!(test_row.shash(test_table, &cipher), 17259954866336786813);
         assert_eq!(
-            test_field.shash_debug(test_table, test_row, "hi", &cipher),
-            14565685931123352409
+            test_field
+                .shash_debug(test_table, test_row, "hi", &cipher)
+                .unwrap(),
+            9054111691838703432
         );
         assert_eq!(
-            test_field.shash(test_table, test_row, &cipher),
-            14565685931123352409
+            test_field.shash(test_table, test_row, &cipher).unwrap(),
+            9054111691838703432
         );
     }
     #[test]
     fn f_search_test() {
         let test_table = jadb::Table {
-            path: "tests/test_dir/test_db",
+            path: "tests/test_dir/test_search",
             id: 0,
         };
-        let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
-        let mut hasher: Vec<Vec<std::collections::HashMap<String, usize>>> =
-            vec![vec![std::collections::HashMap::new()]];
-        assert_eq!(jadb::init(test_table, &mut hasher, &cipher), 0);
+        let (_, cipher) = a_setup(test_table.path, &["hi", "you\nhi", "you"]);
+        let mut hasher = HashStorage::new();
+        assert!(jadb::init(test_table, &mut hasher, &cipher).is_ok());
+        let hit = |table, row, field| jadb::Hit {
+            table,
+            row: jadb::Row { pos: row },
+            field: jadb::Field { pos: field },
+        };
         assert_eq!(
             test_table.search(String::from("hi"), &hasher),
-            vec![0, 0, 0]
+            vec![hit(0, 0, 0), hit(0, 1, 1)]
+        );
+        assert!(test_table.search(String::from("none"), &hasher).is_empty());
+
+        // all tables are searched
+        let other_table = jadb::Table {
+            path: "tests/test_dir/test_search_other",
+            id: 1,
+        };
+        a_delete(other_table.path);
+        other_table.create().unwrap();
+        jadb::init(other_table, &mut hasher, &cipher).unwrap();
+        other_table
+            .write("hi", jadb::Row { pos: 2 }, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(
+            jadb::search(String::from("hi"), &hasher),
+            vec![hit(0, 0, 0), hit(0, 1, 1), hit(1, 2, 0)]
+        );
+        assert_eq!(
+            jadb::search(String::from("you"), &hasher),
+            vec![hit(0, 1, 0), hit(0, 2, 0)]
         );
-        assert_eq!(jadb::search(String::from("hi"), &hasher), vec![0, 0, 0]);
     }
     #[test]
     fn g_test_delete() {
         let test_table = jadb::Table {
-            path: "tests/test_dir/test_db",
+            path: "tests/test_dir/test_delete",
             id: 0,
         };
         let test_row = jadb::Row { pos: 0 };
         let test_field = jadb::Field { pos: 1 };
-        let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
-        let mut hasher: Vec<Vec<std::collections::HashMap<String, usize>>> =
-            vec![vec![std::collections::HashMap::new()]];
+        let (mut hasher, cipher) = a_setup(test_table.path, &["hi"]);
 
-        jadb::init(test_table, &mut hasher, &cipher);
-        let row_path = format!("{}/{}", test_table.path, test_row.pos);
         let w_res = test_table.write("|o\na", test_row, &mut hasher, &cipher);
-        assert_eq!(w_res, 0);
+        assert!(w_res.is_ok());
         let del_f = test_field.delete(test_table, test_row, &mut hasher, &cipher);
-        assert_eq!(del_f, 0);
-        assert_eq!(test_table.read(test_row, &cipher), vec![String::from("hi")]);
+        assert!(del_f.is_ok());
+        assert_eq!(
+            test_table.read(test_row, &cipher).unwrap(),
+            vec![String::from("hi")]
+        );
+        assert!(matches!(
+            test_field.delete(test_table, test_row, &mut hasher, &cipher),
+            Err(jadb::JadbError::IndexOutOfRange { index: 1, len: 1 })
+        ));
 
         let del_r = test_row.delete(test_table, &mut hasher);
-        assert_eq!(del_r, 0);
-        assert_eq!(Path::new(&row_path).exists(), false);
-        assert_eq!(test_row.delete(test_table, &mut hasher), 1);
+        assert!(del_r.is_ok());
+        let (pos, record) = a_records(test_table.path).pop().unwrap();
+        assert_eq!((pos, record[12], record.len()), (0, 1, 13 + 8)); // a deleted record without a row
+        assert!(matches!(
+            test_row.delete(test_table, &mut hasher),
+            Err(jadb::JadbError::NotFound(_))
+        ));
+
+        // the same value in several fields and rows is found everywhere until it is deleted
+        let hit = |row, field| jadb::Hit {
+            table: 0,
+            row: jadb::Row { pos: row },
+            field: jadb::Field { pos: field },
+        };
+        test_table
+            .write("a\nb\na", jadb::Row { pos: 1 }, &mut hasher, &cipher)
+            .unwrap();
+        test_table
+            .write("a", jadb::Row { pos: 2 }, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(hasher.get("a"), vec![hit(1, 0), hit(1, 2), hit(2, 0)]);
+        jadb::Field { pos: 0 }
+            .delete(test_table, jadb::Row { pos: 1 }, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(hasher.get("a"), vec![hit(1, 1), hit(2, 0)]);
+        assert_eq!(hasher.get("b"), vec![hit(1, 0)]);
+        jadb::Row { pos: 2 }
+            .delete(test_table, &mut hasher)
+            .unwrap();
+        assert_eq!(hasher.get("a"), vec![hit(1, 1)]);
+        test_table
+            .write("c", jadb::Row { pos: 1 }, &mut hasher, &cipher)
+            .unwrap();
+        assert!(hasher.get("a").is_empty());
 
         let del_t = test_table.delete(&mut hasher);
-        assert_eq!(del_t, 0);
-        assert_eq!(Path::new(&test_table.path).exists(), false);
+        assert!(del_t.is_ok());
+        assert!(hasher.get("c").is_empty());
+        assert!(!Path::new(&test_table.path).exists());
 
-        assert_eq!(test_table.delete(&mut hasher), 1);
+        assert!(matches!(
+            test_table.delete(&mut hasher),
+            Err(jadb::JadbError::NotFound(_))
+        ));
     }
     #[test]
     fn h_test_split_by_delimiter() {
@@ -171,20 +325,2007 @@ mod tests {
         // test internal
         let input = [5, 1, 2, 3, 44, 1, 4, 44, 4, 7, 8, 44, 7];
         assert_eq!(
-            vec![
-                &[5, 1, 2, 3][..],
-                &[1, 4][..],
-                &[4, 7, 8][..],
-                &[7][..]
-            ],
+            vec![&[5, 1, 2, 3][..], &[1, 4][..], &[4, 7, 8][..], &[7][..]],
             jadb::split_by_delim(&input, &44u8)
         );
 
-        // test external
+        // test external, empty fields are kept
         let input = [0, 1, 0, 2, 0];
         assert_eq!(
-            vec![&[1][..], &[2][..], &[][..]],
+            vec![&[][..], &[1][..], &[2][..], &[][..]],
             jadb::split_by_delim(&input, &0u8)
         );
+        assert_eq!(
+            vec![&[][..] as &[u8], &[], &[]],
+            jadb::split_by_delim(&[7, 7], &7u8)
+        );
+    }
+    #[test]
+    fn i_test_database() {
+        let root = "tests/test_dir/test_database";
+        a_delete(root);
+        let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+        let mut db = jadb::Database::open(root, cipher).expect("Couldn't open database");
+        {
+            let mut users = db.create_table("users").expect("Couldn't create table");
+            users.write("hi\nyou", jadb::Row { pos: 0 }).unwrap();
+        }
+        db.create_table("posts").unwrap();
+        assert!(matches!(
+            db.create_table("users"),
+            Err(jadb::JadbError::AlreadyExists(_))
+        ));
+        for name in ["../users", "users\n0: posts", "tab\tle"] {
+            assert!(matches!(
+                db.create_table(name),
+                Err(jadb::JadbError::InvalidName(_))
+            ));
+        }
+        assert!(matches!(
+            db.table("comments"),
+            Err(jadb::JadbError::NotFound(_))
+        ));
+        assert_eq!(db.table_names(), vec!["users", "posts"]);
+        drop(db);
+
+        // reopening initializes all tables again
+        let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+        let mut db = jadb::Database::open(root, cipher).expect("Couldn't reopen database");
+        let mut users = db.table("users").unwrap();
+        assert_eq!(
+            users.read(jadb::Row { pos: 0 }).unwrap(),
+            vec![String::from("hi"), String::from("you")]
+        );
+        assert_eq!(
+            users.search(String::from("you")),
+            vec![jadb::Hit {
+                table: users.table().id,
+                row: jadb::Row { pos: 0 },
+                field: jadb::Field { pos: 1 }
+            }]
+        );
+        users
+            .delete_field(jadb::Row { pos: 0 }, jadb::Field { pos: 0 })
+            .unwrap();
+        assert!(users.search(String::from("hi")).is_empty());
+        users.delete_row(jadb::Row { pos: 0 }).unwrap();
+        db.drop_table("users").unwrap();
+        assert_eq!(db.table_names(), vec!["posts"]);
+        assert!(!Path::new(&format!("{}/users", root)).exists());
+    }
+    #[test]
+    fn j_test_catalog() {
+        let root = "tests/test_dir/test_catalog";
+        a_delete(root);
+        let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+        let mut db = jadb::Database::open(root, cipher).expect("Couldn't open database");
+        assert_eq!(db.create_table("users").unwrap().table().id, 0);
+        assert_eq!(db.create_table("posts").unwrap().table().id, 1);
+        db.drop_table("users").unwrap();
+        assert_eq!(db.create_table("users").unwrap().table().id, 2); // ids aren't reused
+        assert!(Path::new(&format!("{}/catalog.jadb", root)).exists());
+        drop(db);
+
+        let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+        let mut db = jadb::Database::open(root, cipher).expect("Couldn't reopen database");
+        assert_eq!(db.table("posts").unwrap().table().id, 1);
+        assert_eq!(db.table("users").unwrap().table().id, 2);
+
+        // a table can't be used with another id than the one it was created with
+        let path = format!("{}/posts", root);
+        let table = jadb::Table { path: &path, id: 0 };
+        let mut hasher = HashStorage::new();
+        assert!(matches!(
+            jadb::init(table, &mut hasher, db.cipher()),
+            Err(jadb::JadbError::TableIdMismatch { expected: 0, .. })
+        ));
+    }
+    #[test]
+    fn k_test_migrate() {
+        let test_table = jadb::Table {
+            path: "tests/test_dir/test_migrate",
+            id: 0,
+        };
+        let (_, cipher) = a_setup(test_table.path, &[]);
+        let row_path = format!("{}/{}", test_table.path, 0);
+        // 'hi' in the file of row 0 of table 0, written with a nonce derived from table id and row position
+        fs::write(
+            &row_path,
+            vec![
+                63, 47, 135, 212, 103, 39, 146, 86, 145, 189, 43, 116, 98, 114, 112, 53, 101, 179,
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            test_table.read(jadb::Row { pos: 0 }, &cipher).unwrap(),
+            vec![String::from("hi")]
+        );
+        assert_eq!(test_table.migrate(&cipher).unwrap(), 1);
+        assert!(!Path::new(&row_path).exists()); // moved to the segment file
+        assert_eq!(
+            a_row(&a_records(test_table.path).pop().unwrap().1).len(),
+            4 + 1 + 8 + 8 + 4 + 1 + 12 + 1 + 4 + 2 + 16
+        );
+        assert_eq!(
+            test_table.read(jadb::Row { pos: 0 }, &cipher).unwrap(),
+            vec![String::from("hi")]
+        );
+        assert_eq!(test_table.migrate(&cipher).unwrap(), 0);
+
+        // rows and tables of newer versions aren't read
+        let mut newer = a_row(&a_records(test_table.path).pop().unwrap().1).to_vec();
+        newer[4] = 7;
+        fs::write(format!("{}/{}", test_table.path, 1), newer).unwrap();
+        assert!(matches!(
+            test_table.read(jadb::Row { pos: 1 }, &cipher),
+            Err(jadb::JadbError::UnsupportedFormat(_))
+        ));
+        let info_path = format!("{}/info.jadb", test_table.path);
+        let info = fs::read_to_string(&info_path).unwrap();
+        fs::write(
+            &info_path,
+            info.replace("format version: 6", "format version: 7"),
+        )
+        .unwrap();
+        let mut hasher = HashStorage::new();
+        assert!(matches!(
+            jadb::init(test_table, &mut hasher, &cipher),
+            Err(jadb::JadbError::UnsupportedFormat(_))
+        ));
+    }
+    #[test]
+    fn l_test_misplaced() {
+        let test_table = jadb::Table {
+            path: "tests/test_dir/test_misplaced",
+            id: 0,
+        };
+        let (_, cipher) = a_setup(test_table.path, &["hi", "you"]);
+        let row_2 = format!("{}/{}", test_table.path, 2);
+
+        // a row copied to another position is rejected
+        let copied = a_row(&a_records(test_table.path)[1].1).to_vec();
+        fs::write(&row_2, &copied).unwrap();
+        assert!(matches!(
+            test_table.read(jadb::Row { pos: 2 }, &cipher),
+            Err(jadb::JadbError::Misplaced {
+                table: 0,
+                row: 1,
+                ..
+            })
+        ));
+        let other_table = jadb::Table {
+            path: test_table.path,
+            id: 1,
+        };
+        assert!(matches!(
+            other_table.read(jadb::Row { pos: 1 }, &cipher),
+            Err(jadb::JadbError::Misplaced {
+                table: 0,
+                row: 1,
+                ..
+            })
+        ));
+
+        // changing the header to match fails authentication
+        let mut forged = copied;
+        forged[13..21].copy_from_slice(&2u64.to_le_bytes());
+        fs::write(&row_2, forged).unwrap();
+        assert!(matches!(
+            test_table.read(jadb::Row { pos: 2 }, &cipher),
+            Err(jadb::JadbError::Decrypt(_))
+        ));
+    }
+    #[test]
+    fn m_test_passphrase() {
+        let root = "tests/test_dir/test_passphrase";
+        a_delete(root);
+        let mut db = jadb::Database::open_with_passphrase(root, "secret").unwrap();
+        db.create_table("users")
+            .unwrap()
+            .write("hi", jadb::Row { pos: 0 })
+            .unwrap();
+        drop(db);
+        assert!(Path::new(&format!("{}/key.jadb", root)).exists());
+
+        assert!(matches!(
+            jadb::Database::open_with_passphrase(root, "wrong"),
+            Err(jadb::JadbError::WrongPassphrase)
+        ));
+        let db = jadb::Database::open_with_passphrase(root, "secret").unwrap();
+        db.change_passphrase("secret", "new secret").unwrap();
+        drop(db);
+        assert!(matches!(
+            jadb::Database::open_with_passphrase(root, "secret"),
+            Err(jadb::JadbError::WrongPassphrase)
+        ));
+        let mut db = jadb::Database::open_with_passphrase(root, "new secret").unwrap();
+        assert_eq!(
+            db.table("users")
+                .unwrap()
+                .read(jadb::Row { pos: 0 })
+                .unwrap(),
+            vec![String::from("hi")]
+        );
+
+        // the keyfile can be kept outside of the database
+        let other_root = "tests/test_dir/test_keyfile";
+        let keyfile = "tests/test_dir/test_keyfile.key";
+        a_delete(other_root);
+        let _ = fs::remove_file(keyfile);
+        jadb::Database::open_with_keyfile(other_root, keyfile, "secret").unwrap();
+        assert!(Path::new(keyfile).exists());
+        assert!(!Path::new(&format!("{}/key.jadb", other_root)).exists());
+        assert!(matches!(
+            jadb::Database::open_with_keyfile(other_root, keyfile, "wrong"),
+            Err(jadb::JadbError::WrongPassphrase)
+        ));
+
+        // no keyfile is left behind for a database encrypted with another key
+        let plain_root = "tests/test_dir/test_passphrase_plain";
+        a_delete(plain_root);
+        let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+        jadb::Database::open(plain_root, cipher)
+            .unwrap()
+            .create_table("users")
+            .unwrap()
+            .write("hi", jadb::Row { pos: 0 })
+            .unwrap();
+        assert!(jadb::Database::open_with_passphrase(plain_root, "secret").is_err());
+        assert!(!Path::new(&format!("{}/key.jadb", plain_root)).exists());
+        a_delete(plain_root);
+    }
+    #[test]
+    fn n_test_rotate_key() {
+        let test_table = jadb::Table {
+            path: "tests/test_dir/test_rotate_key",
+            id: 0,
+        };
+        let (_, old) = a_setup(test_table.path, &["hi", "you", "all"]);
+        let new = Aes128Gcm::new(Key::from_slice(b"q3t6w9z$C&F)J@Nc"));
+        let segment_0 = format!("{}/segment-0.jadb", test_table.path);
+
+        // a rotation interrupted before row 1 and compacting leaves a table with mixed keys
+        let untouched = fs::read(&segment_0).unwrap();
+        assert_eq!(test_table.rotate_key(&old, &new).unwrap(), 3);
+        let rotated: Vec<u8> = a_records(test_table.path)
+            .into_iter()
+            .filter(|(pos, _)| *pos != 1)
+            .flat_map(|(_, record)| record)
+            .collect();
+        fs::remove_file(format!("{}/segment-1.jadb", test_table.path)).unwrap();
+        fs::write(
+            &segment_0,
+            [&untouched[..], &rotated, b"half written"].concat(),
+        )
+        .unwrap();
+        assert!(matches!(
+            test_table.read(jadb::Row { pos: 1 }, &new),
+            Err(jadb::JadbError::UnknownKey { .. })
+        ));
+        assert!(matches!(
+            test_table.read(jadb::Row { pos: 0 }, &old),
+            Err(jadb::JadbError::UnknownKey { .. })
+        ));
+        for (pos, content) in ["hi", "you", "all"].iter().enumerate() {
+            assert_eq!(
+                test_table
+                    .read_with_keys(jadb::Row { pos }, &[&new, &old])
+                    .unwrap(),
+                vec![String::from(*content)]
+            );
+        }
+
+        // rotating again only re-encrypts the rest
+        assert_eq!(test_table.rotate_key(&old, &new).unwrap(), 1);
+        assert_eq!(test_table.rotate_key(&old, &new).unwrap(), 0);
+        let mut hasher = HashStorage::new();
+        jadb::init(test_table, &mut hasher, &new).unwrap();
+        assert_eq!(
+            test_table.read(jadb::Row { pos: 1 }, &new).unwrap(),
+            vec![String::from("you")]
+        );
+
+        // an interrupted rotation of a database is finished on open
+        let root = "tests/test_dir/test_rotate_key_db";
+        a_delete(root);
+        let mut db = jadb::Database::open(root, old.clone()).unwrap();
+        db.create_table("users")
+            .unwrap()
+            .write("hi", jadb::Row { pos: 0 })
+            .unwrap();
+        db.table("users")
+            .unwrap()
+            .write("you", jadb::Row { pos: 1 })
+            .unwrap();
+        let users = format!("{}/users", root);
+        let untouched = fs::read(format!("{}/segment-0.jadb", users)).unwrap();
+        assert_eq!(db.rotate_key(new.clone()).unwrap(), 2);
+        drop(db);
+        fs::remove_file(format!("{}/segment-1.jadb", users)).unwrap();
+        fs::write(format!("{}/segment-0.jadb", users), untouched).unwrap();
+        assert!(matches!(
+            jadb::Database::open(root, new.clone()),
+            Err(jadb::JadbError::UnknownKey { .. })
+        ));
+        let mut db = jadb::Database::open_rotating(root, &old, new.clone()).unwrap();
+        assert_eq!(
+            db.table("users")
+                .unwrap()
+                .read(jadb::Row { pos: 1 })
+                .unwrap(),
+            vec![String::from("you")]
+        );
+        drop(db);
+        jadb::Database::open(root, new).unwrap();
+
+        // the data key of a database with a passphrase is rotated through its keyfile
+        let root = "tests/test_dir/test_rotate_data_key";
+        a_delete(root);
+        let mut db = jadb::Database::open_with_passphrase(root, "secret").unwrap();
+        db.create_table("users")
+            .unwrap()
+            .write("hi", jadb::Row { pos: 0 })
+            .unwrap();
+        let users = format!("{}/users", root);
+        let row_0 = a_row(&a_records(&users).pop().unwrap().1).to_vec();
+        assert!(matches!(
+            db.rotate_key(old),
+            Err(jadb::JadbError::KeyfileInUse(_))
+        ));
+        assert_eq!(db.rotate_data_key("secret").unwrap(), 1);
+        assert_ne!(
+            row_0[21..25],
+            a_row(&a_records(&users).pop().unwrap().1)[21..25]
+        ); // key id changed
+        drop(db);
+        let keyfile = fs::read_to_string(format!("{}/key.jadb", root)).unwrap();
+        assert!(!keyfile.contains("old wrapped key"));
+        let mut db = jadb::Database::open_with_passphrase(root, "secret").unwrap();
+        assert_eq!(
+            db.table("users")
+                .unwrap()
+                .read(jadb::Row { pos: 0 })
+                .unwrap(),
+            vec![String::from("hi")]
+        );
+
+        // a rotation which wasn't finished, e.g. by another process, is finished by opening the database again
+        let key_path = format!("{}/key.jadb", root);
+        let keyfile = fs::read_to_string(&key_path).unwrap();
+        let wrapped_key = keyfile
+            .lines()
+            .find_map(|line| line.strip_prefix("wrapped key: "))
+            .unwrap();
+        fs::write(
+            &key_path,
+            format!("{}\nold wrapped key: {}", keyfile, wrapped_key),
+        )
+        .unwrap();
+        assert!(matches!(
+            db.rotate_data_key("secret"),
+            Err(jadb::JadbError::RotationInProgress)
+        ));
+        drop(db);
+        let mut db = jadb::Database::open_with_passphrase(root, "secret").unwrap();
+        assert!(!fs::read_to_string(&key_path)
+            .unwrap()
+            .contains("old wrapped key"));
+        assert_eq!(db.rotate_data_key("secret").unwrap(), 1);
+    }
+    #[test]
+    fn o_test_ciphers() {
+        let root = "tests/test_dir/test_ciphers";
+        a_delete(root);
+        fs::create_dir_all(root).unwrap();
+        let aes_256 = Aes256Gcm::new(aes_gcm::Key::from_slice(
+            b"Zr4u7x!A%D*G-KaPdSgVkYp3s6v9y$B&",
+        ));
+        let xchacha = XChaCha20Poly1305::new(chacha20poly1305::Key::from_slice(
+            b"Zr4u7x!A%D*G-KaPdSgVkYp3s6v9y$B&",
+        ));
+        let ciphers: [(jadb::Algorithm, &dyn jadb::RowCipher); 3] = [
+            (jadb::Algorithm::Aes256Gcm, &aes_256),
+            (jadb::Algorithm::XChaCha20Poly1305, &xchacha),
+            (jadb::Algorithm::Plaintext, &jadb::Plaintext),
+        ];
+        for (id, (algorithm, cipher)) in ciphers.into_iter().enumerate() {
+            let path = format!("{}/{}", root, algorithm.name());
+            let table = jadb::Table { path: &path, id };
+            let mut hasher = HashStorage::new();
+            table.create_with(algorithm).unwrap();
+            table
+                .write("hi\nyou", jadb::Row { pos: 0 }, &mut hasher, cipher)
+                .unwrap();
+            assert_eq!(table.algorithm().unwrap(), algorithm);
+            let (_, record) = a_records(&path).pop().unwrap();
+            assert_eq!(
+                a_row(&record).len(),
+                4 + 1
+                    + 8
+                    + 8
+                    + 4
+                    + 1
+                    + algorithm.nonce_len()
+                    + 5
+                    + 2
+                    + 5
+                    + 3
+                    + if id == 2 { 8 } else { 16 }
+            ); // header, encoded texts and tag or checksum
+            let mut hasher = HashStorage::new();
+            jadb::init(table, &mut hasher, cipher).unwrap();
+            assert_eq!(
+                table.read(jadb::Row { pos: 0 }, cipher).unwrap(),
+                vec![String::from("hi"), String::from("you")]
+            );
+        }
+
+        // the algorithm of the table is picked from its info file
+        let cipher = Aes128Gcm::new(Key::from_slice(b"Zr4u7x!A%D*G-KaP"));
+        let plaintext = jadb::Table {
+            path: &format!("{}/plaintext", root),
+            id: 2,
+        };
+        let mut hasher = HashStorage::new();
+        jadb::init(plaintext, &mut hasher, &cipher).unwrap();
+        assert_eq!(
+            plaintext.read(jadb::Row { pos: 0 }, &cipher).unwrap(),
+            vec![String::from("hi"), String::from("you")]
+        );
+        let segment_0 = format!("{}/plaintext/segment-0.jadb", root);
+        let content = fs::read(&segment_0).unwrap();
+        assert!(content.windows(3).any(|window| window == b"you")); // not encrypted
+        let mut tampered = content.clone();
+        let last = tampered.len() - 17; // before the checksums of the row and the record
+        tampered[last] ^= 1;
+        fs::write(&segment_0, tampered).unwrap();
+        assert!(matches!(
+            plaintext.read(jadb::Row { pos: 0 }, &cipher),
+            Err(jadb::JadbError::Corrupt(_))
+        ));
+        let aes_256_table = jadb::Table {
+            path: &format!("{}/aes-256-gcm", root),
+            id: 0,
+        };
+        assert!(matches!(
+            jadb::init(aes_256_table, &mut hasher, &cipher),
+            Err(jadb::JadbError::CipherMismatch {
+                expected: jadb::Algorithm::Aes256Gcm,
+                found: jadb::Algorithm::Aes128Gcm
+            })
+        ));
+        assert!(matches!(
+            aes_256_table.read(jadb::Row { pos: 0 }, &xchacha),
+            Err(jadb::JadbError::CipherMismatch { .. })
+        ));
+
+        // a plaintext row planted in an encrypted table is rejected
+        let forger = jadb::Table {
+            path: &format!("{}/forger", root),
+            id: 0,
+        };
+        forger.create_with(jadb::Algorithm::Plaintext).unwrap();
+        forger
+            .write(
+                "forged",
+                jadb::Row { pos: 1 },
+                &mut hasher,
+                &jadb::Plaintext,
+            )
+            .unwrap();
+        let forged = a_row(&a_records(forger.path).pop().unwrap().1).to_vec();
+        fs::write(format!("{}/1", aes_256_table.path), forged).unwrap();
+        assert!(matches!(
+            aes_256_table.read(jadb::Row { pos: 1 }, &aes_256),
+            Err(jadb::JadbError::Decrypt(_))
+        ));
+        fs::remove_file(format!("{}/1", aes_256_table.path)).unwrap();
+
+        // rotating the key can change the algorithm
+        assert_eq!(aes_256_table.rotate_key(&aes_256, &xchacha).unwrap(), 1);
+        assert_eq!(
+            aes_256_table.algorithm().unwrap(),
+            jadb::Algorithm::XChaCha20Poly1305
+        );
+        jadb::init(aes_256_table, &mut hasher, &xchacha).unwrap();
+
+        // databases can keep tables without sensitive data unencrypted
+        let db_root = "tests/test_dir/test_ciphers_db";
+        a_delete(db_root);
+        let mut db = jadb::Database::open(db_root, xchacha).unwrap();
+        db.create_table("users")
+            .unwrap()
+            .write("hi", jadb::Row { pos: 0 })
+            .unwrap();
+        db.create_table_with("countries", jadb::Algorithm::Plaintext)
+            .unwrap()
+            .write("Iceland", jadb::Row { pos: 0 })
+            .unwrap();
+        assert!(matches!(
+            db.create_table_with("posts", jadb::Algorithm::Aes256Gcm),
+            Err(jadb::JadbError::CipherMismatch { .. })
+        ));
+        assert_eq!(db.rotate_key(aes_256).unwrap(), 1); // only the encrypted table
+        drop(db);
+        let mut db = jadb::Database::open(
+            db_root,
+            Aes256Gcm::new(aes_gcm::Key::from_slice(
+                b"Zr4u7x!A%D*G-KaPdSgVkYp3s6v9y$B&",
+            )),
+        )
+        .unwrap();
+        assert_eq!(
+            db.table("countries")
+                .unwrap()
+                .read(jadb::Row { pos: 0 })
+                .unwrap(),
+            vec![String::from("Iceland")]
+        );
+    }
+    #[test]
+    fn p_test_index_file() {
+        let path = "tests/test_dir/test_index_file";
+        let long = "a field long enough that a byte in the middle of its row can be changed";
+        let (_, cipher) = a_setup(path, &[long, "hi\nyou"]);
+        let table = jadb::Table { path, id: 0 };
+        let index = format!("{}/index.jadb", path);
+        assert!(Path::new(&index).exists());
+
+        // rows which didn't change are taken from the index without reading them
+        let segment_0 = format!("{}/segment-0.jadb", path);
+        let content = fs::read(&segment_0).unwrap();
+        let mut tampered = content.clone();
+        tampered[5 + a_records(path)[0].1.len() / 2] ^= 1; // in the middle of row 0
+        fs::write(&segment_0, &tampered).unwrap();
+        let mut hasher = HashStorage::new();
+        jadb::init(table, &mut hasher, &cipher).unwrap();
+        assert_eq!(hasher.get(long).len(), 1);
+        assert!(matches!(
+            table.read(jadb::Row { pos: 0 }, &cipher),
+            Err(jadb::JadbError::Corrupt(_))
+        ));
+        fs::write(&segment_0, &content).unwrap();
+
+        // a row restored from a backup is read again
+        let backup = fs::read(&segment_0).unwrap();
+        table
+            .write("hey", jadb::Row { pos: 1 }, &mut hasher, &cipher)
+            .unwrap();
+        fs::write(&segment_0, backup).unwrap();
+        let mut hasher = HashStorage::new();
+        jadb::init(table, &mut hasher, &cipher).unwrap();
+        assert!(hasher.get("hey").is_empty());
+        assert_eq!(hasher.get("you").len(), 1);
+
+        // deleted rows are dropped from the index
+        jadb::Row { pos: 1 }.delete(table, &mut hasher).unwrap();
+        let mut hasher = HashStorage::new();
+        jadb::init(table, &mut hasher, &cipher).unwrap();
+        assert!(hasher.get("hi").is_empty());
+
+        // a broken index file is rebuilt from the rows
+        fs::write(&index, b"JADX garbage").unwrap();
+        let mut hasher = HashStorage::new();
+        jadb::init(table, &mut hasher, &cipher).unwrap();
+        assert_eq!(hasher.get(long).len(), 1);
+        assert!(fs::read(&index).unwrap().starts_with(b"JADX\x02"));
+
+        // an index written with another key isn't used
+        let other = Aes128Gcm::new(Key::from_slice(b"KaPdSgVkYp3s6v9y"));
+        assert_eq!(table.rotate_key(&cipher, &other).unwrap(), 1);
+        let mut hasher = HashStorage::new();
+        jadb::init(table, &mut hasher, &other).unwrap();
+        assert_eq!(hasher.get(long).len(), 1);
+        a_delete(path);
+    }
+    #[test]
+    fn q_test_search_patterns() {
+        let path = "tests/test_dir/test_search_patterns";
+        let (mut hasher, cipher) = a_setup(path, &["Anna\nSmith", "Annabel\nJones", "Bob\nAnnan"]);
+        let table = jadb::Table { path, id: 0 };
+        let hit = |row, field| jadb::Hit {
+            table: 0,
+            row: jadb::Row { pos: row },
+            field: jadb::Field { pos: field },
+        };
+
+        assert_eq!(
+            table.search_prefix(String::from("Anna"), &hasher),
+            vec![hit(0, 0), hit(1, 0), hit(2, 1)]
+        );
+        assert!(table
+            .search_prefix(String::from("anna"), &hasher)
+            .is_empty());
+        assert_eq!(jadb::search_prefix(String::new(), &hasher).len(), 6); // every field starts with the empty string
+
+        assert_eq!(
+            table.search_contains(String::from("nna"), &hasher),
+            vec![hit(0, 0), hit(1, 0), hit(2, 1)]
+        );
+        assert_eq!(
+            table.search_contains(String::from("o"), &hasher),
+            vec![hit(1, 1), hit(2, 0)]
+        ); // too short for trigrams
+        assert_eq!(
+            table.search_contains(String::from("nnab"), &hasher).len(),
+            1
+        );
+        assert!(table
+            .search_contains(String::from("bel Jones"), &hasher)
+            .is_empty()); // not within one field
+
+        assert_eq!(
+            table
+                .search_regex(String::from("^Ann[a-z]*$"), &hasher)
+                .unwrap(),
+            vec![hit(0, 0), hit(1, 0), hit(2, 1)]
+        );
+        assert_eq!(
+            table
+                .search_regex(String::from("(?i)smith|JONES"), &hasher)
+                .unwrap(),
+            vec![hit(0, 1), hit(1, 1)]
+        );
+        assert_eq!(
+            table.search_regex(String::from("n+abel"), &hasher).unwrap(),
+            vec![hit(1, 0)]
+        );
+        assert!(matches!(
+            table.search_regex(String::from("(unclosed"), &hasher),
+            Err(jadb::JadbError::InvalidRegex(_))
+        ));
+
+        // the trigrams follow changes of the rows
+        table
+            .write("Anabel\n|o", jadb::Row { pos: 1 }, &mut hasher, &cipher)
+            .unwrap();
+        assert!(table
+            .search_contains(String::from("nnab"), &hasher)
+            .is_empty());
+        assert_eq!(
+            table.search_contains(String::from("nab"), &hasher),
+            vec![hit(1, 0)]
+        );
+        jadb::Row { pos: 2 }.delete(table, &mut hasher).unwrap();
+        assert_eq!(
+            jadb::search_regex(String::from("nna"), &hasher).unwrap(),
+            vec![hit(0, 0)]
+        );
+
+        // other tables aren't searched
+        let other_path = "tests/test_dir/test_search_patterns_other";
+        a_delete(other_path);
+        let other = jadb::Table {
+            path: other_path,
+            id: 1,
+        };
+        other.create().unwrap();
+        other
+            .write("Annika", jadb::Row { pos: 0 }, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(table.search_prefix(String::from("Ann"), &hasher).len(), 1);
+        assert_eq!(jadb::search_prefix(String::from("Ann"), &hasher).len(), 2);
+        a_delete(path);
+        a_delete(other_path);
+    }
+    #[test]
+    fn r_test_full_text() {
+        let path = "tests/test_dir/test_full_text";
+        let (mut hasher, cipher) = a_setup(
+            path,
+            &[
+                "The quick brown fox\njumps over the lazy dog",
+                "Foxes hunting foxes",
+            ],
+        );
+        let table = jadb::Table { path, id: 0 };
+        assert!(matches!(
+            table.search_text(String::from("fox"), &hasher),
+            Err(jadb::JadbError::NoFullText(_))
+        ));
+
+        // words are lowercased, but not stemmed by default
+        table
+            .enable_full_text(jadb::FullText::default(), &mut hasher)
+            .unwrap();
+        let hits = table.search_text(String::from("FOX"), &hasher).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].highlights, vec![16..19]);
+        assert!(table
+            .search_text(String::from("the"), &hasher)
+            .unwrap()
+            .iter()
+            .all(|text_hit| text_hit.hit.row == jadb::Row { pos: 0 }));
+
+        // the options are saved in the info file and used by init
+        let full_text = jadb::FullText {
+            stemming: true,
+            stopwords: true,
+        };
+        table.enable_full_text(full_text, &mut hasher).unwrap();
+        let mut hasher = HashStorage::new();
+        jadb::init(table, &mut hasher, &cipher).unwrap();
+        assert_eq!(hasher.full_text(0), Some(full_text));
+        assert!(table
+            .search_text(String::from("the"), &hasher)
+            .unwrap()
+            .is_empty());
+        let hits = table.search_text(String::from("fox"), &hasher).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].hit.row, jadb::Row { pos: 1 }); // found twice in a short field
+        assert_eq!(hits[0].highlights, vec![0..5, 14..19]);
+        assert!(hits[0].score > hits[1].score);
+
+        // rare words count more
+        let hits = table
+            .search_text(String::from("lazy foxes"), &hasher)
+            .unwrap();
+        assert_eq!(hits[0].hit.field, jadb::Field { pos: 1 });
+        assert_eq!(hits[0].highlights, vec![15..19]);
+
+        // the index follows changes of the rows
+        table
+            .write(
+                "|o\nsleeping dogs",
+                jadb::Row { pos: 0 },
+                &mut hasher,
+                &cipher,
+            )
+            .unwrap();
+        assert!(table
+            .search_text(String::from("lazy"), &hasher)
+            .unwrap()
+            .is_empty());
+        jadb::Row { pos: 1 }.delete(table, &mut hasher).unwrap();
+        let hits = jadb::search_text(String::from("fox sleep"), &hasher);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].hit.field, jadb::Field { pos: 1 }); // 'sleeping dogs' is shorter
+        assert_eq!(hits[0].highlights, vec![0..8]);
+
+        table.disable_full_text(&mut hasher).unwrap();
+        let mut hasher = HashStorage::new();
+        jadb::init(table, &mut hasher, &cipher).unwrap();
+        assert_eq!(hasher.full_text(0), None);
+        a_delete(path);
+    }
+    #[test]
+    fn s_test_normalization() {
+        let path = "tests/test_dir/test_normalization";
+        let (mut hasher, cipher) =
+            a_setup(path, &["Cafe\u{301}\nCAF\u{c9}", "caf\u{e9}\n\u{fb01}le"]);
+        let table = jadb::Table { path, id: 0 };
+        assert_eq!(table.search(String::from("caf\u{e9}"), &hasher).len(), 1); // exact by default
+
+        let options = jadb::IndexOptions {
+            case_folding: true,
+            normalization: jadb::Normalization::Nfc,
+        };
+        table.set_index_options(options, &mut hasher).unwrap();
+        assert_eq!(hasher.index_options(0), options);
+        assert_eq!(table.search(String::from("caf\u{e9}"), &hasher).len(), 3);
+        assert_eq!(table.search(String::from("Cafe\u{301}"), &hasher).len(), 3);
+        assert_eq!(table.search_prefix(String::from("CA"), &hasher).len(), 3);
+        assert_eq!(
+            table
+                .search_contains(String::from("AF\u{c9}"), &hasher)
+                .len(),
+            3
+        );
+        assert_eq!(
+            table
+                .search_regex(String::from("^Caf\u{e9}$"), &hasher)
+                .unwrap()
+                .len(),
+            3
+        );
+        assert!(table
+            .search_regex(String::from("\\W"), &hasher)
+            .unwrap()
+            .is_empty()); // escapes aren't lowercased
+        assert!(table.search(String::from("file"), &hasher).is_empty()); // only nfkc splits ligatures
+
+        // the options are saved in the info file and used by init and write
+        let mut hasher = HashStorage::new();
+        jadb::init(table, &mut hasher, &cipher).unwrap();
+        assert_eq!(hasher.index_options(0), options);
+        table
+            .write("|o\nCaf\u{e9}s", jadb::Row { pos: 1 }, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(table.search(String::from("CAF\u{c9}S"), &hasher).len(), 1);
+        assert_eq!(
+            table.read(jadb::Row { pos: 1 }, &cipher).unwrap(),
+            vec![String::from("caf\u{e9}"), String::from("Caf\u{e9}s")]
+        ); // rows keep the values as written
+
+        // other tables are searched with their own options
+        let other_path = "tests/test_dir/test_normalization_other";
+        a_delete(other_path);
+        let other = jadb::Table {
+            path: other_path,
+            id: 1,
+        };
+        other.create().unwrap();
+        other
+            .write(
+                "caf\u{e9}\nCaf\u{e9}\n\u{fb01}le",
+                jadb::Row { pos: 0 },
+                &mut hasher,
+                &cipher,
+            )
+            .unwrap();
+        let hits = jadb::search(String::from("caf\u{e9}"), &hasher);
+        assert_eq!(hits.len(), 4);
+        assert_eq!(
+            hits[3],
+            jadb::Hit {
+                table: 1,
+                row: jadb::Row { pos: 0 },
+                field: jadb::Field { pos: 0 }
+            }
+        );
+        other
+            .set_index_options(
+                jadb::IndexOptions {
+                    case_folding: false,
+                    normalization: jadb::Normalization::Nfkc,
+                },
+                &mut hasher,
+            )
+            .unwrap();
+        assert_eq!(other.search(String::from("file"), &hasher).len(), 1);
+        assert_eq!(jadb::search_prefix(String::from("Caf"), &hasher).len(), 5); // four in the first table, which ignores case
+
+        // resetting the options removes them from the info file
+        table
+            .set_index_options(jadb::IndexOptions::default(), &mut hasher)
+            .unwrap();
+        assert_eq!(table.search(String::from("caf\u{e9}"), &hasher).len(), 1);
+        let mut hasher = HashStorage::new();
+        jadb::init(table, &mut hasher, &cipher).unwrap();
+        assert_eq!(hasher.index_options(0), jadb::IndexOptions::default());
+        a_delete(path);
+        a_delete(other_path);
+    }
+    #[test]
+    fn t_test_fuzzy() {
+        let path = "tests/test_dir/test_fuzzy";
+        let (mut hasher, cipher) = a_setup(
+            path,
+            &[
+                "Smith\nSmyth",
+                "Schmidt\nSmithers",
+                "mith\nSmith\nSmit\u{e9}",
+            ],
+        );
+        let table = jadb::Table { path, id: 0 };
+        assert_eq!(
+            table.search_fuzzy(String::from("Smith"), 0, &hasher).len(),
+            2
+        ); // exact matches
+        assert_eq!(
+            table.search_fuzzy(String::from("Smiht"), 1, &hasher).len(),
+            0
+        ); // a transposition is two edits
+        let hits = table.search_fuzzy(String::from("Smiht"), 2, &hasher);
+        assert_eq!(hits.len(), 3); // 'Smith' twice and 'Smité'
+        assert_eq!(
+            hits[0],
+            jadb::Hit {
+                table: 0,
+                row: jadb::Row { pos: 0 },
+                field: jadb::Field { pos: 0 }
+            }
+        );
+        assert_eq!(
+            hasher.values_within("Smith", 1),
+            vec![("Smith", 0), ("Smit\u{e9}", 1), ("Smyth", 1), ("mith", 1)]
+        );
+        assert!(hasher
+            .values_within("", 3)
+            .iter()
+            .all(|(value, distance)| value.chars().count() == *distance));
+        assert_eq!(
+            table
+                .search_fuzzy(String::from("Schmitt"), 3, &hasher)
+                .len(),
+            4
+        ); // 'Schmidt', 'Smith' twice and 'Smité'
+
+        // fuzzy searches use the index options of the table
+        table
+            .set_index_options(
+                jadb::IndexOptions {
+                    case_folding: true,
+                    normalization: jadb::Normalization::Nfc,
+                },
+                &mut hasher,
+            )
+            .unwrap();
+        assert_eq!(
+            table
+                .search_fuzzy(String::from("SMITE\u{301}"), 0, &hasher)
+                .len(),
+            1
+        );
+        assert_eq!(
+            jadb::search_fuzzy(String::from("smithers"), 1, &hasher).len(),
+            1
+        );
+        table
+            .write("Smithe", jadb::Row { pos: 3 }, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(
+            jadb::search_fuzzy(String::from("smithers"), 2, &hasher).len(),
+            2
+        );
+        a_delete(path);
+    }
+    #[test]
+    fn u_test_values() {
+        use jadb::Value;
+
+        let path = "tests/test_dir/test_values";
+        let (mut hasher, cipher) = a_setup(path, &[]);
+        let table = jadb::Table { path, id: 0 };
+        let row = jadb::Row { pos: 0 };
+        let timestamp = chrono::TimeZone::timestamp_opt(&chrono::Utc, 1_700_000_000, 123_456_789)
+            .single()
+            .unwrap();
+        let values = vec![
+            Value::Null,
+            Value::Bool(true),
+            Value::Int(i64::MIN),
+            Value::Float(0.1 + 0.2),
+            Value::Text(String::from("two\nlines")),
+            Value::Bytes(vec![0, 10, 255]),
+            Value::Timestamp(timestamp),
+        ];
+        table
+            .write_values(&values, row, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(table.read_values(row, &cipher).unwrap(), values); // exactly as written
+        assert_eq!(
+            table.read(row, &cipher).unwrap(),
+            vec![
+                "",
+                "true",
+                "-9223372036854775808",
+                "0.30000000000000004",
+                "two\nlines",
+                "000aff",
+                "2023-11-14T22:13:20.123456789Z"
+            ]
+        );
+        assert!(matches!(
+            table.write_values(&[], row, &mut hasher, &cipher),
+            Err(jadb::JadbError::EmptyContent)
+        ));
+
+        // values are found by their text form, also after init from the index file
+        let mut hasher = HashStorage::new();
+        jadb::init(table, &mut hasher, &cipher).unwrap();
+        assert_eq!(table.search(String::from("two\nlines"), &hasher).len(), 1);
+        assert_eq!(table.search(String::from("000aff"), &hasher).len(), 1);
+
+        // '|o' and deleting fields keep the types of the other values
+        table
+            .write("|o\n|o\n|o\nnew", row, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(
+            table.read_values(row, &cipher).unwrap(),
+            vec![
+                Value::Null,
+                Value::Bool(true),
+                Value::Int(i64::MIN),
+                Value::from("new")
+            ]
+        );
+        jadb::Field { pos: 0 }
+            .delete(table, row, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(
+            table.read_values(row, &cipher).unwrap()[..2],
+            [Value::Bool(true), Value::Int(i64::MIN)]
+        );
+
+        // numbers are compared by value, no matter if they are integers or floats
+        let mut sorted = vec![
+            Value::from("1"),
+            Value::Float(f64::NAN),
+            Value::Int(2),
+            Value::Float(1.5),
+            Value::Null,
+            Value::Int(1),
+            Value::Float(1.0),
+            Value::Bool(false),
+        ];
+        sorted.sort();
+        assert_eq!(
+            sorted,
+            vec![
+                Value::Null,
+                Value::Bool(false),
+                Value::Int(1),
+                Value::Float(1.0),
+                Value::Float(1.5),
+                Value::Int(2),
+                Value::Float(f64::NAN),
+                Value::from("1"),
+            ]
+        );
+        assert_eq!(Value::Int(1), Value::Float(1.0));
+        assert!(Value::Int(i64::MAX) < Value::Float(9_223_372_036_854_775_807.0)); // rounded up to 2^63
+        assert!(Value::Int(-3) < Value::Float(-2.5));
+        a_delete(path);
+    }
+    #[test]
+    fn v_test_schema() {
+        use jadb::{Column, ColumnType, JadbError, Schema, Value};
+
+        let path = "tests/test_dir/test_schema";
+        let (mut hasher, cipher) = a_setup(path, &["Anna\n42"]);
+        let table = jadb::Table { path, id: 0 };
+        let row = |pos| jadb::Row { pos };
+        let column = |name: &str, column_type, nullable, default| Column {
+            name: name.to_string(),
+            column_type,
+            nullable,
+            default,
+        };
+        let schema = Schema {
+            columns: vec![
+                column("name", ColumnType::Text, false, None),
+                column("age", ColumnType::Int, false, Some(Value::Int(0))),
+                column("score", ColumnType::Float, true, None),
+                column(
+                    "note",
+                    ColumnType::Text,
+                    false,
+                    Some(Value::from("a\\b\nc default d")),
+                ),
+            ],
+        };
+
+        // rows written before must already match, "42" is a text
+        assert!(matches!(
+            table.set_schema(schema.clone(), &hasher),
+            Err(JadbError::SchemaViolation(_))
+        ));
+        table
+            .write_values(
+                &[
+                    Value::from("Anna"),
+                    Value::Int(42),
+                    Value::Null,
+                    Value::from("hi"),
+                ],
+                row(0),
+                &mut hasher,
+                &cipher,
+            )
+            .unwrap();
+        table.set_schema(schema.clone(), &hasher).unwrap();
+        assert_eq!(table.schema().unwrap(), Some(schema.clone())); // saved in the info file, defaults included
+
+        // fields are parsed as their column's type, missing ones get defaults
+        table
+            .write("Ben\n\n1.5", row(1), &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(
+            table.read_values(row(1), &cipher).unwrap(),
+            vec![
+                Value::from("Ben"),
+                Value::Int(0),
+                Value::Float(1.5),
+                Value::from("a\\b\nc default d")
+            ]
+        );
+        table
+            .write_values(
+                &[Value::from("Carl"), Value::Int(7), Value::Int(3)],
+                row(2),
+                &mut hasher,
+                &cipher,
+            )
+            .unwrap();
+        assert_eq!(
+            table.read_values(row(2), &cipher).unwrap()[2],
+            Value::Float(3.0)
+        ); // integers are converted to floats
+        for content in ["Dora\nold", "Dora\n1\nhigh", "Dora\n1\n1\nx\ny"] {
+            assert!(matches!(
+                table.write(content, row(3), &mut hasher, &cipher),
+                Err(JadbError::SchemaViolation(_))
+            ));
+        }
+        assert!(matches!(
+            table.write_values(
+                &[Value::from("Dora"), Value::Null],
+                row(3),
+                &mut hasher,
+                &cipher
+            ),
+            Err(JadbError::SchemaViolation(_))
+        ));
+        assert!(!Path::new(&format!("{}/3", path)).exists());
+
+        // fields can be looked up by name
+        let score = table.field("score").unwrap();
+        assert_eq!(score, jadb::Field { pos: 2 });
+        assert!(matches!(
+            table.field("height"),
+            Err(JadbError::UnknownColumn(_))
+        ));
+        score.delete(table, row(1), &mut hasher, &cipher).unwrap(); // nullable
+        assert_eq!(table.read_values(row(1), &cipher).unwrap()[2], Value::Null);
+        assert!(matches!(
+            table
+                .field("name")
+                .unwrap()
+                .delete(table, row(1), &mut hasher, &cipher),
+            Err(JadbError::SchemaViolation(_))
+        ));
+
+        // invalid schemas are rejected, an empty one removes it
+        for columns in [
+            vec![column("first name", ColumnType::Text, false, None)],
+            vec![
+                column("a", ColumnType::Int, false, None),
+                column("a", ColumnType::Int, false, None),
+            ],
+            vec![column("a", ColumnType::Int, false, Some(Value::from("0")))],
+        ] {
+            assert!(table.set_schema(Schema { columns }, &hasher).is_err());
+        }
+        table.set_schema(Schema::default(), &hasher).unwrap();
+        assert_eq!(table.schema().unwrap(), None);
+        table.write("old", row(0), &mut hasher, &cipher).unwrap();
+
+        // database tables
+        let root = "tests/test_dir/test_schema_db";
+        a_delete(root);
+        let mut db = jadb::Database::open(root, cipher).unwrap();
+        let mut users = db.create_table("users").unwrap();
+        users.set_schema(schema).unwrap();
+        users.write("Eve", row(0)).unwrap();
+        let age = users.field("age").unwrap();
+        assert_eq!(users.read_values(row(0)).unwrap()[age.pos], Value::Int(0));
+        a_delete(root);
+        a_delete(path);
+    }
+    #[test]
+    fn w_test_range() {
+        use jadb::{Column, ColumnType, Schema, Value};
+        use std::ops::Bound;
+
+        let path = "tests/test_dir/test_range";
+        let (mut hasher, cipher) = a_setup(path, &[]);
+        let table = jadb::Table { path, id: 0 };
+        let score = jadb::Field { pos: 1 };
+        table
+            .set_schema(
+                Schema {
+                    columns: vec![
+                        Column {
+                            name: String::from("name"),
+                            column_type: ColumnType::Text,
+                            nullable: false,
+                            default: None,
+                        },
+                        Column {
+                            name: String::from("score"),
+                            column_type: ColumnType::Float,
+                            nullable: true,
+                            default: None,
+                        },
+                    ],
+                },
+                &hasher,
+            )
+            .unwrap();
+        for (pos, content) in ["a\n10", "b\n-2.5", "c\n50", "d", "e\n10", "f\n7"]
+            .iter()
+            .enumerate()
+        {
+            table
+                .write(content, jadb::Row { pos }, &mut hasher, &cipher)
+                .unwrap();
+        }
+        let rows = |hits: Vec<jadb::Hit>| -> Vec<usize> {
+            assert!(hits.iter().all(|hit| hit.field == score));
+            hits.iter().map(|hit| hit.row.pos).collect()
+        };
+
+        assert_eq!(rows(table.range(score, 10..50, &hasher)), vec![0, 4]);
+        assert_eq!(rows(table.range(score, 10..=50, &hasher)), vec![0, 4, 2]);
+        assert_eq!(rows(table.range(score, -3.0..7.0, &hasher)), vec![1]);
+        assert_eq!(rows(table.range(score, ..10, &hasher)), vec![1, 5]);
+        assert_eq!(
+            rows(table.range(score, (Bound::Excluded(10), Bound::Unbounded), &hasher)),
+            vec![2]
+        );
+        assert_eq!(
+            rows(table.range::<Value>(score, .., &hasher)),
+            vec![1, 5, 0, 4, 2]
+        ); // nulls aren't indexed
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 50..10;
+        assert!(table.range(score, reversed, &hasher).is_empty());
+        assert!(table
+            .range(score, (Bound::Excluded(10), Bound::Excluded(10)), &hasher)
+            .is_empty());
+        assert!(table
+            .range::<Value>(jadb::Field { pos: 0 }, .., &hasher)
+            .is_empty()); // texts
+
+        // rewritten and deleted rows move, also after init
+        table
+            .write("f\n100", jadb::Row { pos: 5 }, &mut hasher, &cipher)
+            .unwrap();
+        jadb::Row { pos: 0 }.delete(table, &mut hasher).unwrap();
+        let mut hasher = HashStorage::new();
+        jadb::init(table, &mut hasher, &cipher).unwrap();
+        assert_eq!(rows(table.range(score, 0.., &hasher)), vec![4, 2, 5]);
+
+        // open ends stay within numbers or timestamps
+        table.set_schema(Schema::default(), &hasher).unwrap();
+        let time = |secs| {
+            Value::Timestamp(
+                chrono::TimeZone::timestamp_opt(&chrono::Utc, secs, 0)
+                    .single()
+                    .unwrap(),
+            )
+        };
+        table
+            .write_values(
+                &[Value::from("g"), time(1_000)],
+                jadb::Row { pos: 6 },
+                &mut hasher,
+                &cipher,
+            )
+            .unwrap();
+        assert_eq!(rows(table.range(score, 0.., &hasher)), vec![4, 2, 5]);
+        assert_eq!(rows(table.range(score, ..time(2_000), &hasher)), vec![6]);
+        assert_eq!(rows(table.range(score, time(0).., &hasher)), vec![6]);
+
+        // -0.0 is the same number as 0.0 and 0
+        for (pos, zero) in [
+            (7, Value::Float(-0.0)),
+            (8, Value::Float(0.0)),
+            (9, Value::Int(0)),
+        ] {
+            table
+                .write_values(
+                    &[Value::from("h"), zero],
+                    jadb::Row { pos },
+                    &mut hasher,
+                    &cipher,
+                )
+                .unwrap();
+        }
+        for zero in [Value::Float(-0.0), Value::Float(0.0), Value::Int(0)] {
+            assert_eq!(
+                rows(table.range(score, zero.clone()..=zero, &hasher)),
+                vec![7, 8, 9]
+            );
+        }
+        assert_eq!(Value::Float(-0.0), Value::Float(0.0));
+
+        // database tables
+        let root = "tests/test_dir/test_range_db";
+        a_delete(root);
+        let mut db = jadb::Database::open(root, cipher).unwrap();
+        let mut prices = db.create_table("prices").unwrap();
+        prices
+            .write_values(&[Value::Float(9.99)], jadb::Row { pos: 0 })
+            .unwrap();
+        assert_eq!(prices.range(jadb::Field { pos: 0 }, 5..10).len(), 1);
+        a_delete(root);
+        a_delete(path);
+    }
+    #[test]
+    fn x_test_binary_safe() {
+        use jadb::Value;
+
+        let path = "tests/test_dir/test_binary_safe";
+        let (mut hasher, cipher) = a_setup(path, &["\nx\n"]);
+        let table = jadb::Table { path, id: 0 };
+        let row = jadb::Row { pos: 1 };
+        assert_eq!(
+            table.read(jadb::Row { pos: 0 }, &cipher).unwrap(),
+            vec!["", "x", ""]
+        ); // empty fields at both ends
+
+        let values = vec![
+            Value::from(""),
+            Value::from("a\nb"),
+            Value::from("\n"),
+            Value::Bytes(vec![0, 10, 255, 10]),
+            Value::from("|o"),
+            Value::from("\u{0}\r\n\u{10ffff}"),
+            Value::from(""),
+        ];
+        table
+            .write_values(&values, row, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(table.read_values(row, &cipher).unwrap(), values);
+        assert_eq!(table.read(row, &cipher).unwrap()[1], "a\nb");
+
+        // the same fields as strings
+        let fields = ["", "a\nb", "\n", "|o", "\u{0}\r\n\u{10ffff}", ""];
+        let string_row = jadb::Row { pos: 2 };
+        table
+            .write_fields(&fields, string_row, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(table.read(string_row, &cipher).unwrap(), fields);
+        assert!(matches!(
+            table.write_fields(&[], string_row, &mut hasher, &cipher),
+            Err(jadb::JadbError::EmptyContent)
+        ));
+        string_row.delete(table, &mut hasher).unwrap();
+        assert_eq!(
+            jadb::Field { pos: 2 }.length(table, row, &cipher).unwrap(),
+            1
+        );
+        assert_eq!(
+            table.search(String::from("a\nb"), &hasher),
+            vec![jadb::Hit {
+                table: 0,
+                row,
+                field: jadb::Field { pos: 1 }
+            }]
+        );
+
+        // the same after reading the rows again, with and without the index file
+        for remove_index in [false, true] {
+            if remove_index {
+                fs::remove_file(format!("{}/index.jadb", path)).unwrap();
+            }
+            let mut hasher = HashStorage::new();
+            jadb::init(table, &mut hasher, &cipher).unwrap();
+            assert_eq!(table.search(String::from("\n"), &hasher).len(), 1);
+            assert_eq!(table.search(String::from(""), &hasher).len(), 4);
+        }
+        assert_eq!(table.read_values(row, &cipher).unwrap(), values);
+        a_delete(path);
+    }
+    #[test]
+    fn y_test_updates() {
+        use jadb::{Column, ColumnType, JadbError, Schema, Update, Value};
+
+        let path = "tests/test_dir/test_updates";
+        let (mut hasher, cipher) = a_setup(path, &["a\n5\nx\nz"]);
+        let table = jadb::Table { path, id: 0 };
+        let row = jadb::Row { pos: 0 };
+
+        // operators of update_fields, old fields after the last one are removed
+        table
+            .update_fields("|o\n|+2\n|ab\n|r", row, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(table.read(row, &cipher).unwrap(), vec!["a", "7", "xb"]);
+        table
+            .update_fields("|inew\n|o\n|-0.5\n||o\n|ab", row, &mut hasher, &cipher)
+            .unwrap_err(); // there is no fourth old field to append to
+        table
+            .update_fields("|inew\n|o\n|-0.5\n||o", row, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(
+            table.read_values(row, &cipher).unwrap(),
+            vec![
+                Value::from("new"),
+                Value::from("a"),
+                Value::from("6.5"), // numbers written as text stay text
+                Value::from("|o")
+            ]
+        );
+        assert_eq!(table.search(String::from("6.5"), &hasher).len(), 1);
+
+        // failed updates don't change the row
+        for (content, invalid_update) in [
+            ("|+one", true),
+            ("|+1", true), // "new" isn't a number
+            ("|o\n|o\n|o\n|o\n|o", false),
+        ] {
+            let result = table.update_fields(content, row, &mut hasher, &cipher);
+            match invalid_update {
+                true => assert!(matches!(result, Err(JadbError::InvalidUpdate(_)))),
+                false => assert!(matches!(result, Err(JadbError::IndexOutOfRange { .. }))),
+            }
+        }
+        assert_eq!(table.read(row, &cipher).unwrap()[0], "new");
+        assert!(matches!(
+            table.update_fields("|r", jadb::Row { pos: 9 }, &mut hasher, &cipher),
+            Err(JadbError::IndexOutOfRange { .. })
+        ));
+
+        // write only keeps old fields with '|o', everything else is written as it is
+        table
+            .write("|o\n|+2\n||o\n|r", row, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(
+            table.read(row, &cipher).unwrap(),
+            vec!["new", "|+2", "||o", "|r"]
+        );
+        let new_row = jadb::Row { pos: 9 };
+        table
+            .write("|o\n|a", new_row, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(table.read(new_row, &cipher).unwrap(), vec!["|o", "|a"]); // there is no old field to keep
+        new_row.delete(table, &mut hasher).unwrap();
+
+        // typed updates keep the fields after the last one
+        let row = jadb::Row { pos: 1 };
+        assert!(matches!(
+            table.update(&[Update::Keep], row, &mut hasher, &cipher),
+            Err(JadbError::NotFound(_))
+        ));
+        table
+            .write_values(
+                &[
+                    Value::Int(i64::MAX - 1),
+                    Value::Float(1.5),
+                    Value::Bytes(vec![1]),
+                    Value::from("rest"),
+                ],
+                row,
+                &mut hasher,
+                &cipher,
+            )
+            .unwrap();
+        table
+            .update(
+                &[
+                    Update::Increment(Value::Int(1)),
+                    Update::Decrement(Value::Int(2)),
+                    Update::Append(Value::Bytes(vec![2])),
+                ],
+                row,
+                &mut hasher,
+                &cipher,
+            )
+            .unwrap();
+        assert_eq!(
+            table.read_values(row, &cipher).unwrap(),
+            vec![
+                Value::Int(i64::MAX),
+                Value::Float(-0.5),
+                Value::Bytes(vec![1, 2]),
+                Value::from("rest")
+            ]
+        );
+        assert!(matches!(
+            table.update(
+                &[Update::Increment(Value::Int(1))],
+                row,
+                &mut hasher,
+                &cipher
+            ),
+            Err(JadbError::InvalidUpdate(_))
+        )); // overflow
+        assert!(matches!(
+            table.update(
+                &[Update::Keep, Update::Keep, Update::Append(Value::from("x"))],
+                row,
+                &mut hasher,
+                &cipher
+            ),
+            Err(JadbError::InvalidUpdate(_))
+        ));
+        table
+            .update(
+                &[
+                    Update::Remove,
+                    Update::Insert(Value::Bool(true)),
+                    Update::Null,
+                    Update::Default,
+                ],
+                row,
+                &mut hasher,
+                &cipher,
+            )
+            .unwrap();
+        assert_eq!(
+            table.read_values(row, &cipher).unwrap(),
+            vec![
+                Value::Bool(true),
+                Value::Null,
+                Value::Null,
+                Value::from("rest")
+            ]
+        );
+        assert!(matches!(
+            table.update(&vec![Update::Remove; 4], row, &mut hasher, &cipher),
+            Err(JadbError::EmptyContent)
+        ));
+
+        // operators follow the schema
+        let path = "tests/test_dir/test_updates_schema";
+        let (mut hasher, cipher) = a_setup(path, &[]);
+        let table = jadb::Table { path, id: 0 };
+        let column = |name: &str, column_type, default| Column {
+            name: name.to_string(),
+            column_type,
+            nullable: false,
+            default,
+        };
+        table
+            .set_schema(
+                Schema {
+                    columns: vec![
+                        column("name", ColumnType::Text, None),
+                        column("visits", ColumnType::Int, Some(Value::Int(0))),
+                    ],
+                },
+                &hasher,
+            )
+            .unwrap();
+        table
+            .write("Anna\n3", jadb::Row { pos: 0 }, &mut hasher, &cipher)
+            .unwrap();
+        table
+            .update_fields("|o\n|+1", jadb::Row { pos: 0 }, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(
+            table.read_values(jadb::Row { pos: 0 }, &cipher).unwrap()[1],
+            Value::Int(4)
+        );
+        table
+            .update_fields("|o\n|d", jadb::Row { pos: 0 }, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(
+            table.read_values(jadb::Row { pos: 0 }, &cipher).unwrap()[1],
+            Value::Int(0)
+        );
+        assert!(matches!(
+            table.update_fields("|o\n|n", jadb::Row { pos: 0 }, &mut hasher, &cipher),
+            Err(JadbError::SchemaViolation(_))
+        ));
+        a_delete(path);
+
+        // database tables
+        let root = "tests/test_dir/test_updates_db";
+        a_delete(root);
+        let mut db = jadb::Database::open(root, cipher).unwrap();
+        let mut counters = db.create_table("counters").unwrap();
+        counters
+            .write_values(&[Value::Int(1)], jadb::Row { pos: 0 })
+            .unwrap();
+        counters
+            .update(&[Update::Increment(Value::Int(1))], jadb::Row { pos: 0 })
+            .unwrap();
+        assert_eq!(counters.read(jadb::Row { pos: 0 }).unwrap(), vec!["2"]);
+        a_delete(root);
+        a_delete("tests/test_dir/test_updates");
+    }
+    #[test]
+    fn z_test_field_updates() {
+        use jadb::{Column, ColumnType, JadbError, Schema, Value};
+
+        let path = "tests/test_dir/test_field_updates";
+        let (mut hasher, cipher) = a_setup(path, &["a\nb"]);
+        let table = jadb::Table { path, id: 0 };
+        let row = jadb::Row { pos: 0 };
+        let field = |pos| jadb::Field { pos };
+
+        field(1)
+            .set(table, row, Value::Float(2.5), &mut hasher, &cipher)
+            .unwrap();
+        field(0)
+            .insert(table, row, "start", &mut hasher, &cipher)
+            .unwrap();
+        field(3)
+            .insert(table, row, "end", &mut hasher, &cipher)
+            .unwrap(); // right after the last field
+        row.append_field(table, true, &mut hasher, &cipher).unwrap();
+        assert_eq!(
+            table.read_values(row, &cipher).unwrap(),
+            vec![
+                Value::from("start"),
+                Value::from("a"),
+                Value::Float(2.5),
+                Value::from("end"),
+                Value::Bool(true)
+            ]
+        );
+        assert!(matches!(
+            field(5).set(table, row, "x", &mut hasher, &cipher),
+            Err(JadbError::IndexOutOfRange { index: 5, len: 5 })
+        ));
+        assert!(matches!(
+            field(6).insert(table, row, "x", &mut hasher, &cipher),
+            Err(JadbError::IndexOutOfRange { index: 6, len: 5 })
+        ));
+        assert!(matches!(
+            jadb::Row { pos: 1 }.append_field(table, "x", &mut hasher, &cipher),
+            Err(JadbError::NotFound(_))
+        ));
+
+        // the hash storage and the index file stay in sync
+        assert!(table.search(String::from("b"), &hasher).is_empty());
+        assert_eq!(
+            table.search(String::from("end"), &hasher),
+            vec![jadb::Hit {
+                table: 0,
+                row,
+                field: field(3)
+            }]
+        );
+        assert_eq!(table.range(field(2), 2..3, &hasher).len(), 1);
+        let mut hasher = HashStorage::new();
+        jadb::init(table, &mut hasher, &cipher).unwrap();
+        assert_eq!(table.search(String::from("true"), &hasher).len(), 1);
+
+        // values must match the schema
+        let path = "tests/test_dir/test_field_updates_schema";
+        let (mut hasher, cipher) = a_setup(path, &[]);
+        let table = jadb::Table { path, id: 0 };
+        table
+            .set_schema(
+                Schema {
+                    columns: vec![Column {
+                        name: String::from("age"),
+                        column_type: ColumnType::Int,
+                        nullable: false,
+                        default: None,
+                    }],
+                },
+                &hasher,
+            )
+            .unwrap();
+        table.write("1", row, &mut hasher, &cipher).unwrap();
+        let age = table.field("age").unwrap();
+        age.set(table, row, 2, &mut hasher, &cipher).unwrap();
+        assert!(matches!(
+            age.set(table, row, "3", &mut hasher, &cipher),
+            Err(JadbError::SchemaViolation(_))
+        ));
+        assert!(matches!(
+            row.append_field(table, 3, &mut hasher, &cipher),
+            Err(JadbError::SchemaViolation(_))
+        ));
+        assert_eq!(table.read(row, &cipher).unwrap(), vec!["2"]);
+        a_delete(path);
+
+        // database tables
+        let root = "tests/test_dir/test_field_updates_db";
+        a_delete(root);
+        let mut db = jadb::Database::open(root, cipher).unwrap();
+        let mut users = db.create_table("users").unwrap();
+        users.write("Anna", row).unwrap();
+        users.append_field(row, 42).unwrap();
+        users.insert_field(row, field(1), "Smith").unwrap();
+        users.set_field(row, field(0), "Ann").unwrap();
+        assert_eq!(users.read(row).unwrap(), vec!["Ann", "Smith", "42"]);
+        a_delete(root);
+        a_delete("tests/test_dir/test_field_updates");
+    }
+    #[test]
+    fn za_test_insert() {
+        use jadb::JadbError;
+
+        let path = "tests/test_dir/test_insert";
+        let (mut hasher, cipher) = a_setup(path, &["a", "b"]);
+        let table = jadb::Table { path, id: 0 };
+        let insert = |content, hasher: &mut HashStorage| {
+            table.insert(content, hasher, &cipher).map(|row| row.pos)
+        };
+
+        assert_eq!(insert("c", &mut hasher).unwrap(), 2); // after the rows written before
+        let info = fs::read_to_string(format!("{}/info.jadb", path)).unwrap();
+        assert!(info.lines().any(|line| line == "next row: 66")); // a block of positions is reserved
+        table
+            .write("e", jadb::Row { pos: 4 }, &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(insert("d", &mut hasher).unwrap(), 3);
+        assert_eq!(insert("f", &mut hasher).unwrap(), 5); // 4 is taken
+        assert!(matches!(
+            insert("", &mut hasher),
+            Err(JadbError::EmptyContent)
+        ));
+        let xchacha = XChaCha20Poly1305::new(chacha20poly1305::Key::from_slice(
+            b"Zr4u7x!A%D*G-KaPdSgVkYp3s6v9y$B&",
+        ));
+        assert!(matches!(
+            table.insert("g", &mut hasher, &xchacha),
+            Err(JadbError::CipherMismatch { .. })
+        ));
+        assert_eq!(insert("h", &mut hasher).unwrap(), 7); // failed inserts use their position as well
+        jadb::Row { pos: 7 }.delete(table, &mut hasher).unwrap();
+        assert_eq!(insert("i", &mut hasher).unwrap(), 8);
+        assert_eq!(
+            table.read(jadb::Row { pos: 8 }, &cipher).unwrap(),
+            vec!["i"]
+        );
+
+        // rows are only written where there is none
+        let row = |pos| jadb::Row { pos };
+        assert!(matches!(
+            table.insert_if_absent("x", row(0), &mut hasher, &cipher),
+            Err(JadbError::AlreadyExists(_))
+        ));
+        assert_eq!(table.read(row(0), &cipher).unwrap(), vec!["a"]);
+        table
+            .insert_if_absent("j", row(7), &mut hasher, &cipher)
+            .unwrap();
+        table
+            .insert_if_absent("k", row(20), &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(table.insert("l", &mut hasher, &cipher).unwrap(), row(21));
+        assert_eq!(table.search(String::from("l"), &hasher)[0].row, row(21));
+        assert_eq!(
+            fs::read_to_string(format!("{}/info.jadb", path)).unwrap(),
+            info
+        ); // inserts in the block don't rewrite the info file
+        assert!(!Path::new(&format!("{}/info.jadb.tmp", path)).exists());
+
+        // positions after the block and of blocks reserved by others
+        table
+            .insert_if_absent("m", row(70), &mut hasher, &cipher)
+            .unwrap();
+        assert_eq!(table.insert("n", &mut hasher, &cipher).unwrap(), row(71));
+        let info = fs::read_to_string(format!("{}/info.jadb", path)).unwrap();
+        fs::write(
+            format!("{}/info.jadb", path),
+            info.replace("next row: 135", "next row: 200"),
+        )
+        .unwrap();
+        assert_eq!(table.insert("o", &mut hasher, &cipher).unwrap(), row(200));
+
+        // database tables
+        let root = "tests/test_dir/test_insert_db";
+        a_delete(root);
+        let mut db = jadb::Database::open(root, cipher).unwrap();
+        let mut users = db.create_table("users").unwrap();
+        assert_eq!(users.insert("Anna").unwrap(), row(0));
+        users.insert_if_absent("Ben", row(1)).unwrap();
+        assert_eq!(users.insert("Carl").unwrap(), row(2));
+        a_delete(root);
+        a_delete(path);
+    }
+    #[test]
+    fn zb_test_rows() {
+        use jadb::JadbError;
+
+        let path = "tests/test_dir/test_rows";
+        let (mut hasher, cipher) = a_setup(path, &[]);
+        let table = jadb::Table { path, id: 0 };
+        let row = |pos| jadb::Row { pos };
+        for pos in [10, 2, 0, 7, 1] {
+            table
+                .write(&format!("row {}", pos), row(pos), &mut hasher, &cipher)
+                .unwrap();
+        }
+
+        // ordered by position, not by file name
+        let rows: Vec<(jadb::Row, Vec<String>)> = table
+            .rows(&cipher)
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+        assert_eq!(
+            rows.iter().map(|(row, _)| row.pos).collect::<Vec<usize>>(),
+            vec![0, 1, 2, 7, 10]
+        );
+        assert_eq!(rows[3].1, vec!["row 7"]);
+        assert_eq!(
+            table.keys().unwrap(),
+            vec![row(0), row(1), row(2), row(7), row(10)]
+        );
+        assert_eq!(table.rows(&cipher).unwrap().size_hint(), (5, Some(5)));
+
+        // pages
+        assert_eq!(table.scan_keys(row(0), 2).unwrap(), vec![row(0), row(1)]);
+        assert_eq!(table.scan_keys(row(3), 2).unwrap(), vec![row(7), row(10)]);
+        assert!(table.scan_keys(row(11), 2).unwrap().is_empty());
+        assert!(table.scan_keys(row(0), 0).unwrap().is_empty());
+        assert_eq!(
+            table.scan(row(2), 10, &cipher).unwrap(),
+            vec![
+                (row(2), vec![String::from("row 2")]),
+                (row(7), vec![String::from("row 7")]),
+                (row(10), vec![String::from("row 10")])
+            ]
+        );
+
+        // keys don't need the key, rows which can't be read give errors
+        let other = Aes128Gcm::new(Key::from_slice(b"q3t6w9z$C&F)J@Nc"));
+        assert!(table.rows(&other).unwrap().all(|row| row.is_err()));
+        assert!(table.scan(row(0), 1, &other).is_err());
+        let mut rows = table.rows(&cipher).unwrap();
+        rows.next().unwrap().unwrap();
+        row(1).delete(table, &mut hasher).unwrap();
+        assert!(matches!(rows.next(), Some(Err(JadbError::NotFound(_)))));
+        assert_eq!(rows.next().unwrap().unwrap().0, row(2));
+        assert!(matches!(
+            jadb::Table {
+                path: "tests/test_dir/test_rows_missing",
+                id: 0
+            }
+            .keys(),
+            Err(JadbError::NotFound(_))
+        ));
+
+        // database tables
+        let root = "tests/test_dir/test_rows_db";
+        a_delete(root);
+        let mut db = jadb::Database::open(root, cipher).unwrap();
+        let mut users = db.create_table("users").unwrap();
+        for name in ["Anna", "Ben", "Carl"] {
+            users.insert(name).unwrap();
+        }
+        assert_eq!(users.keys().unwrap().len(), 3);
+        assert_eq!(users.scan_keys(row(1), 5).unwrap(), vec![row(1), row(2)]);
+        assert_eq!(users.scan(row(2), 1).unwrap()[0].1, vec!["Carl"]);
+        assert_eq!(
+            users
+                .rows()
+                .unwrap()
+                .map(|row| row.unwrap().1.concat())
+                .collect::<Vec<String>>(),
+            vec!["Anna", "Ben", "Carl"]
+        );
+        a_delete(root);
+        a_delete(path);
+    }
+    #[test]
+    fn zc_test_segments() {
+        let path = "tests/test_dir/test_segments";
+        a_delete(path);
+        let table = jadb::Table { path, id: 0 };
+        let mut hasher = HashStorage::new();
+        let row = |pos| jadb::Row { pos };
+        let contains = |text: &[u8]| {
+            a_records(path)
+                .iter()
+                .any(|(_, record)| record.windows(text.len()).any(|window| window == text))
+        };
+        table.create_with(jadb::Algorithm::Plaintext).unwrap(); // so the contents can be found in the segments
+        for pos in 0..100 {
+            table
+                .write(
+                    &format!("row {:03}", pos),
+                    row(pos),
+                    &mut hasher,
+                    &jadb::Plaintext,
+                )
+                .unwrap();
+        }
+        assert_eq!(fs::read_dir(path).unwrap().count(), 4); // info, index, lock and one segment file
+        assert_eq!(table.keys().unwrap().len(), 100);
+
+        // replaced and deleted rows stay in the segments until the table is compacted
+        table
+            .write("changed", row(5), &mut hasher, &jadb::Plaintext)
+            .unwrap();
+        row(6).delete(table, &mut hasher).unwrap();
+        assert!(matches!(
+            row(6).delete(table, &mut hasher),
+            Err(jadb::JadbError::NotFound(_))
+        ));
+        assert_eq!(a_records(path).len(), 102);
+        assert!(contains(b"row 005") && contains(b"row 006"));
+        table.compact().unwrap();
+        assert_eq!(a_records(path).len(), 99);
+        assert!(!contains(b"row 005") && !contains(b"row 006"));
+        assert_eq!(
+            table.read(row(5), &jadb::Plaintext).unwrap(),
+            vec!["changed"]
+        );
+        assert!(matches!(
+            table.read(row(6), &jadb::Plaintext),
+            Err(jadb::JadbError::NotFound(_))
+        ));
+        assert_eq!(table.keys().unwrap().len(), 99);
+
+        // a record which was only written in part is cut off before the next one
+        let segment = format!("{}/segment-1.jadb", path);
+        let compacted = fs::read(&segment).unwrap();
+        fs::write(&segment, [&compacted[..], b"\x10\x00\x00\x00half"].concat()).unwrap();
+        assert_eq!(
+            table.read(row(99), &jadb::Plaintext).unwrap(),
+            vec!["row 099"]
+        );
+        table
+            .write("row 100", row(100), &mut hasher, &jadb::Plaintext)
+            .unwrap();
+        assert_eq!(a_records(path).pop().unwrap().0, 100);
+
+        // changed records are found when they are read, when the segment is read again they are ignored like unfinished ones
+        let mut tampered = fs::read(&segment).unwrap();
+        let last = tampered.len() - 10;
+        tampered[last] ^= 1; // in row 100
+        fs::write(&segment, tampered).unwrap();
+        assert!(matches!(
+            table.read(row(100), &jadb::Plaintext),
+            Err(jadb::JadbError::Corrupt(_))
+        ));
+        assert!(matches!(
+            table.read(row(100), &jadb::Plaintext),
+            Err(jadb::JadbError::NotFound(_))
+        ));
+        row(100).delete(table, &mut hasher).unwrap_err();
+        table
+            .write("row 100", row(100), &mut hasher, &jadb::Plaintext)
+            .unwrap();
+        assert_eq!(a_records(path).len(), 100); // written over the changed record
+        row(0).delete(table, &mut hasher).unwrap();
+        table.compact().unwrap();
+
+        // zeros and a torn record at the end of a segment, e.g. after a crash, are ignored when the table is opened again
+        let segment = format!("{}/segment-2.jadb", path);
+        let compacted = fs::read(&segment).unwrap();
+        let torn = &compacted[compacted.len() - 30..compacted.len() - 3];
+        fs::write(&segment, [&compacted[..], &[0; 64], torn].concat()).unwrap();
+        let reopened_path = format!("{}_reopened", path);
+        a_delete(&reopened_path);
+        fs::rename(path, &reopened_path).unwrap();
+        let reopened = jadb::Table {
+            path: &reopened_path,
+            id: 0,
+        };
+        assert_eq!(reopened.keys().unwrap().len(), 99);
+        assert_eq!(
+            reopened.read(row(1), &jadb::Plaintext).unwrap(),
+            vec!["row 001"]
+        );
+        assert!(matches!(
+            reopened.read(row(0), &jadb::Plaintext),
+            Err(jadb::JadbError::NotFound(_))
+        ));
+        reopened
+            .write("row 000", row(0), &mut hasher, &jadb::Plaintext)
+            .unwrap();
+        assert_eq!(
+            fs::read(format!("{}/segment-2.jadb", reopened_path)).unwrap()[..compacted.len()],
+            compacted[..]
+        );
+        assert_eq!(a_records(&reopened_path).pop().unwrap().0, 0); // the zeros were cut off
+        fs::rename(&reopened_path, path).unwrap();
+
+        // the table is read again from its segments
+        let mut hasher = HashStorage::new();
+        fs::remove_file(format!("{}/index.jadb", path)).unwrap();
+        jadb::init(table, &mut hasher, &jadb::Plaintext).unwrap();
+        assert_eq!(
+            hasher.get("changed"),
+            vec![jadb::Hit {
+                table: 0,
+                row: row(5),
+                field: jadb::Field { pos: 0 }
+            }]
+        );
+        assert_eq!(hasher.get("row 100").len(), 1);
+        assert_eq!(hasher.get("row 000").len(), 1); // written in the reopened table
+        assert_eq!(table.keys().unwrap().len(), 100);
+
+        // another spelling of the path uses the same rows
+        let other_path = format!("./{}", path);
+        let other = jadb::Table {
+            path: &other_path,
+            id: 0,
+        };
+        other
+            .write("row 101", row(101), &mut hasher, &jadb::Plaintext)
+            .unwrap();
+        row(100).delete(other, &mut hasher).unwrap();
+        other.compact().unwrap();
+        assert_eq!(
+            table.read(row(101), &jadb::Plaintext).unwrap(),
+            vec!["row 101"]
+        );
+        assert!(matches!(
+            table.read(row(100), &jadb::Plaintext),
+            Err(jadb::JadbError::NotFound(_))
+        ));
+        assert_eq!(table.keys().unwrap(), other.keys().unwrap());
+        assert!(!fs::read_dir(path).unwrap().any(|entry| entry
+            .unwrap()
+            .file_name()
+            .to_string_lossy()
+            .ends_with(".tmp")));
+        other.delete(&mut hasher).unwrap();
+        table.create_with(jadb::Algorithm::Plaintext).unwrap();
+        assert!(table.keys().unwrap().is_empty());
+        a_delete(path);
     }
 }